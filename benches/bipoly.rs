@@ -0,0 +1,29 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use whirlpool::bipoly::BiPoly;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let elements: Vec<BiPoly> = (0..=u8::MAX).map(BiPoly::new).collect();
+    let mut group = c.benchmark_group("multiply all pairs");
+    group.bench_function("table", |b| {
+        b.iter(|| {
+            for &x in elements.iter() {
+                for &y in elements.iter() {
+                    black_box(black_box(x).mul_table(y));
+                }
+            }
+        })
+    });
+    group.bench_function("bitwise", |b| {
+        b.iter(|| {
+            for &x in elements.iter() {
+                for &y in elements.iter() {
+                    black_box(black_box(x).mul_bitwise(y));
+                }
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main! {benches}
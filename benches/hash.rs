@@ -2,8 +2,9 @@ use criterion::{criterion_group, criterion_main, Criterion};
 
 fn criterion_benchmark(c: &mut Criterion) {
     let message = &[1, 2, 3, 4, 5];
+    let params = whirlpool::params::WhirlpoolParams::mini();
     c.bench_function("hash data", |b| {
-        b.iter(|| whirlpool::core::hash(message.clone().into()))
+        b.iter(|| whirlpool::core::hash(&params, message.to_vec()).unwrap())
     });
 }
 
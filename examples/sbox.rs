@@ -0,0 +1,36 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use whirlpool::constants::{ISO_MATRIX, MATRIX};
+use whirlpool::sbox;
+
+/// Prints the S-box report of mini-Whirlpool next to the ISO Whirlpool and AES ones.
+/// With `--csv DIR` the DDT and LAT of every S-box are written to `DIR/<name>-ddt.csv`
+/// and `DIR/<name>-lat.csv`.
+fn main() -> std::io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let csv_dir = match args.as_slice() {
+        [] => None,
+        [flag, dir] if flag == "--csv" => Some(PathBuf::from(dir)),
+        _ => {
+            eprintln!("Usage: cargo run --example sbox [-- --csv DIR]");
+            std::process::exit(2);
+        }
+    };
+
+    let sboxes = [
+        ("mini", sbox::table(&MATRIX)),
+        ("iso", sbox::table(&ISO_MATRIX)),
+        ("aes", sbox::aes()),
+    ];
+    for (name, table) in sboxes.iter() {
+        println!("{}:\n{}", name, sbox::analyze(table));
+        if let Some(dir) = &csv_dir {
+            let ddt = File::create(dir.join(format!("{}-ddt.csv", name)))?;
+            sbox::write_csv(&sbox::ddt(table), BufWriter::new(ddt))?;
+            let lat = File::create(dir.join(format!("{}-lat.csv", name)))?;
+            sbox::write_csv(&sbox::lat(table), BufWriter::new(lat))?;
+        }
+    }
+    Ok(())
+}
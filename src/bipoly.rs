@@ -1,18 +1,84 @@
-use std::ops::{Add, Mul};
-/// Binary Ring Polynomial element
+use super::constants::MODULO;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+/// Binary Ring Polynomial element, reduced modulo the degree 8 polynomial `M`.
+/// Any irreducible `M` gives the field GF(2^8), for example `constants::MODULO` (0x12B),
+/// `constants::ISO_MODULO` (0x11D) or `constants::AES_MODULO` (0x11B).
+/// Elements are made with [`BiPoly::new`], `ZERO` or `ONE`, which reject a reducible
+/// modulus when the crate using it is built, and so does the arithmetic:
+///
+/// ```compile_fail
+/// use whirlpool::bipoly::BiPoly;
+/// // x^8 + 1 = (x + 1)^8
+/// let _ = BiPoly::<0x101>::new(3);
+/// ```
+///
+/// ```compile_fail
+/// use whirlpool::bipoly::BiPoly;
+/// let _ = BiPoly::<0x101>::ONE;
+/// ```
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
-pub struct BiPoly(pub u8);
+pub struct BiPoly<const M: u16 = MODULO>(pub(crate) u8);
 
-impl std::fmt::Display for BiPoly {
+impl<const M: u16> std::fmt::Display for BiPoly<M> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{}", self.0)
     }
 }
 
-impl BiPoly {
-    fn to_array(&self) -> [bool; 8] {
-        return [
-            self.0 >> 0 & 1 == 1,
+impl<const M: u16> BiPoly<M> {
+    pub const ZERO: BiPoly<M> = BiPoly::new(0);
+    pub const ONE: BiPoly<M> = BiPoly::new(1);
+
+    /// The element whose coefficients are the bits of `value`, x^0 in the lowest one
+    pub const fn new(value: u8) -> BiPoly<M> {
+        let () = Modulus::<M>::IRREDUCIBLE;
+        BiPoly(value)
+    }
+
+    pub const fn value(self) -> u8 {
+        self.0
+    }
+
+    /// Multiplicative inverse, `None` for zero which has no inverse.
+    /// Every non-zero element satisfies `a^255 = 1`, so `a^254` is its inverse.
+    pub fn inverse(self) -> Option<BiPoly<M>> {
+        if self == BiPoly::ZERO {
+            return None;
+        }
+        Some(self.pow(254))
+    }
+
+    /// `self` multiplied `exp` times by itself, by square and multiply. `0^0` is one.
+    pub fn pow(self, exp: u32) -> BiPoly<M> {
+        let mut result = BiPoly::ONE;
+        let mut square = self;
+        let mut exp = exp;
+        while exp != 0 {
+            if exp & 1 == 1 {
+                result *= square;
+            }
+            square *= square;
+            exp >>= 1;
+        }
+        result
+    }
+
+    /// The smallest k > 0 with `self^k == 1`, a divisor of 255.
+    /// `None` for zero, which never reaches one.
+    pub fn order(self) -> Option<u32> {
+        let mut power = self;
+        for k in 1..=255 {
+            if power == BiPoly::ONE {
+                return Some(k);
+            }
+            power *= self;
+        }
+        None
+    }
+
+    fn to_array(self) -> [bool; 8] {
+        [
+            self.0 & 1 == 1,
             self.0 >> 1 & 1 == 1,
             self.0 >> 2 & 1 == 1,
             self.0 >> 3 & 1 == 1,
@@ -20,12 +86,25 @@ impl BiPoly {
             self.0 >> 5 & 1 == 1,
             self.0 >> 6 & 1 == 1,
             self.0 >> 7 & 1 == 1,
-        ];
+        ]
+    }
+
+    fn from_array(bits: &[bool]) -> BiPoly<M> {
+        BiPoly(
+            u8::from(bits[0])
+                | (u8::from(bits[1]) << 1)
+                | (u8::from(bits[2]) << 2)
+                | (u8::from(bits[3]) << 3)
+                | (u8::from(bits[4]) << 4)
+                | (u8::from(bits[5]) << 5)
+                | (u8::from(bits[6]) << 6)
+                | (u8::from(bits[7]) << 7),
+        )
     }
 }
 
-impl Add for BiPoly {
-    type Output = BiPoly;
+impl<const M: u16> Add for BiPoly<M> {
+    type Output = BiPoly<M>;
     /// Adding two elements in a field Z_2[x] can be done with xoring two numbers
     /// Example.
     /// 1111 + 0101
@@ -36,14 +115,208 @@ impl Add for BiPoly {
     /// = x^3 + 0x^2 + 1x + 0
     /// = x^3 + x
     /// 1010 = 1111 ^ 0101
+    #[allow(clippy::suspicious_arithmetic_impl)]
     fn add(self, rhs: Self) -> Self::Output {
+        let () = Modulus::<M>::IRREDUCIBLE;
         BiPoly(self.0 ^ rhs.0)
     }
 }
 
-impl Mul for BiPoly {
-    type Output = BiPoly;
+/// In characteristic 2 every element is its own additive inverse, so subtraction is addition
+impl<const M: u16> Sub for BiPoly<M> {
+    type Output = BiPoly<M>;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + rhs
+    }
+}
+
+impl<const M: u16> Neg for BiPoly<M> {
+    type Output = BiPoly<M>;
+
+    fn neg(self) -> Self::Output {
+        let () = Modulus::<M>::IRREDUCIBLE;
+        self
+    }
+}
+
+impl<const M: u16> Div for BiPoly<M> {
+    type Output = BiPoly<M>;
+
+    /// Multiplies by the inverse of `rhs`, panics if `rhs` is zero
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, rhs: Self) -> Self::Output {
+        self * rhs.inverse().expect("attempt to divide by zero")
+    }
+}
+
+impl<const M: u16> AddAssign for BiPoly<M> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<const M: u16> SubAssign for BiPoly<M> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl<const M: u16> MulAssign for BiPoly<M> {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl<const M: u16> DivAssign for BiPoly<M> {
+    fn div_assign(&mut self, rhs: Self) {
+        *self = *self / rhs;
+    }
+}
+
+impl<const M: u16> std::iter::Sum for BiPoly<M> {
+    fn sum<I: Iterator<Item = BiPoly<M>>>(iter: I) -> BiPoly<M> {
+        iter.fold(BiPoly::ZERO, Add::add)
+    }
+}
+
+impl<'a, const M: u16> std::iter::Sum<&'a BiPoly<M>> for BiPoly<M> {
+    fn sum<I: Iterator<Item = &'a BiPoly<M>>>(iter: I) -> BiPoly<M> {
+        iter.copied().sum()
+    }
+}
+
+impl<const M: u16> std::iter::Product for BiPoly<M> {
+    fn product<I: Iterator<Item = BiPoly<M>>>(iter: I) -> BiPoly<M> {
+        iter.fold(BiPoly::ONE, Mul::mul)
+    }
+}
+
+impl<'a, const M: u16> std::iter::Product<&'a BiPoly<M>> for BiPoly<M> {
+    fn product<I: Iterator<Item = &'a BiPoly<M>>>(iter: I) -> BiPoly<M> {
+        iter.copied().product()
+    }
+}
+
+impl<const M: u16> Mul for BiPoly<M> {
+    type Output = BiPoly<M>;
+
+    #[cfg(not(feature = "bitwise-mul"))]
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.mul_table(rhs)
+    }
+
+    #[cfg(feature = "bitwise-mul")]
     fn mul(self, rhs: Self) -> Self::Output {
+        self.mul_bitwise(rhs)
+    }
+}
+
+/// Tables derived from the modulus `M` by the compiler, once for every modulus in use.
+/// Using them, or `IRREDUCIBLE` directly as `BiPoly::new` does, fails the build if `M` is not
+/// an irreducible polynomial of degree 8, because then `BiPoly<M>` would not be a field.
+struct Modulus<const M: u16>;
+
+impl<const M: u16> Modulus<M> {
+    const IRREDUCIBLE: () = assert!(
+        is_irreducible(M),
+        "The modulus must be an irreducible polynomial of degree 8"
+    );
+
+    /// Every product `a * b` modulo `M` at `[a][b]`, 64 KiB
+    const PRODUCTS: &'static [[u8; 256]; 256] = {
+        let () = Self::IRREDUCIBLE;
+        &mul_table(M)
+    };
+
+    /// x^8 to x^14 reduced modulo `M`
+    const REDUCTIONS: [u8; 7] = {
+        let () = Self::IRREDUCIBLE;
+        reductions(M)
+    };
+}
+
+/// Whether `modulus` is a degree 8 polynomial over Z_2 without a factor of degree 1 to 4,
+/// which is all a reducible degree 8 polynomial could have. This one runs at compile time
+/// for `Modulus`, [`Poly::is_irreducible`](crate::poly::Poly::is_irreducible) is the public test.
+pub(crate) const fn is_irreducible(modulus: u16) -> bool {
+    if modulus >> 8 != 1 {
+        return false;
+    }
+    let mut divisor = 2;
+    while divisor < 32 {
+        if poly_mod(modulus, divisor) == 0 {
+            return false;
+        }
+        divisor += 1;
+    }
+    true
+}
+
+/// Remainder of the division of two polynomials over Z_2, `divisor` must not be zero
+const fn poly_mod(mut dividend: u16, divisor: u16) -> u16 {
+    let degree = 15 - divisor.leading_zeros();
+    while dividend != 0 && 15 - dividend.leading_zeros() >= degree {
+        dividend ^= divisor << (15 - dividend.leading_zeros() - degree);
+    }
+    dividend
+}
+
+/// x^8 is the modulus without its leading term, every next power is the previous one times x
+const fn reductions(modulo: u16) -> [u8; 7] {
+    let mut table = [0u8; 7];
+    table[0] = modulo as u8;
+    let mut i = 1;
+    while i < 7 {
+        table[i] = mul_shift(table[i - 1], 2, modulo);
+        i += 1;
+    }
+    table
+}
+
+const fn mul_table(modulo: u16) -> [[u8; 256]; 256] {
+    let mut table = [[0u8; 256]; 256];
+    let mut a = 0;
+    while a < 256 {
+        let mut b = 0;
+        while b < 256 {
+            table[a][b] = mul_shift(a as u8, b as u8, modulo);
+            b += 1;
+        }
+        a += 1;
+    }
+    table
+}
+
+/// Shift-and-add multiplication: for every bit of `b` add the matching multiple of `a`,
+/// reducing `a * x` by the modulus whenever it reaches degree 8
+const fn mul_shift(a: u8, b: u8, modulo: u16) -> u8 {
+    let mut a = a as u16;
+    let mut b = b;
+    let mut product = 0u16;
+    while b != 0 {
+        if b & 1 == 1 {
+            product ^= a;
+        }
+        a <<= 1;
+        if a & 0x100 != 0 {
+            a ^= modulo;
+        }
+        b >>= 1;
+    }
+    product as u8
+}
+
+impl<const M: u16> BiPoly<M> {
+    /// Multiplication by a single lookup into a table generated from the modulus,
+    /// this is what `*` uses unless the `bitwise-mul` feature is enabled
+    pub fn mul_table(self, rhs: BiPoly<M>) -> BiPoly<M> {
+        BiPoly(Modulus::<M>::PRODUCTS[self.0 as usize][rhs.0 as usize])
+    }
+
+    /// Multiplication on the bits of both factors, kept to check and benchmark the tables
+    pub fn mul_bitwise(self, rhs: BiPoly<M>) -> BiPoly<M> {
         // Split these u8 to array of bits
         let arr = self.to_array();
         let brr = rhs.to_array();
@@ -124,7 +397,9 @@ impl Mul for BiPoly {
         //     }
         // }
 
-        // Since we are working in ring, we need to reduce the output by modulo x^8 + x^5 + x^3 + x + 1
+        // Since we are working in ring, we need to reduce the output by the modulus M.
+        // The steps below use mini-Whirlpool's x^8 + x^5 + x^3 + x + 1 (0x12B) as the example,
+        // `reductions` repeats them for any other modulus.
         // We could just divide the output polynomil by moduolo polynomial using log polynomial
         // division, but it is computationaly expensive; instead, we use simiple substitution.
         // Notice:
@@ -138,83 +413,213 @@ impl Mul for BiPoly {
         // Now each time we find a x^8 in our equation, we can substitute it with x^5 + x^3 + x + 1,
         // thanks to congruency property of modulo. Let's move on.
         // x^9 = x^8 * x = (x^5 + x^3 + x + 1) * x = x^6 + x^4 + x^2 + x (mod x^8 + x^5 + x^3 + x + 1)
-        // x^10 = x^9 * x = (x^6 + x^4 + x^2 + x) * x = x^7 * x^4 * x^3 + x^2 (mod x^8 + x^5 + x^3 + x + 1)
-        // x^11 = x^10 * x = (x^7 * x^4 * x^3 + x^2) * x = x^8 + x^5 + x^4 + x^3 =
+        // x^10 = x^9 * x = (x^6 + x^4 + x^2 + x) * x = x^7 + x^5 + x^3 + x^2 (mod x^8 + x^5 + x^3 + x + 1)
+        // x^11 = x^10 * x = (x^7 + x^5 + x^3 + x^2) * x = x^8 + x^6 + x^4 + x^3 =
         // Notice that we get x^8, so we need to perform the substitution one more time
-        // = (x^5 + x^3 + x + 1) + x^5 + x^4 + x^3 = x^4 + 1 (mod x^8 + x^5 + x^3 + x + 1)
-        // x^12 = x^11 * x = (x^4 + 1) * x = x^5 + x (mod x^8 + x^5 + x^3 + x + 1)
-        // x^13 = x^12 * x = (x^5 + x) * x = x^6 + x^2 (mod x^8 + x^5 + x^3 + x + 1)
-        // x^14 = (x^6 + x^2) * x = x^7 + x^3 (mod x^8 + x^5 + x^3 + x + 1)
+        // = (x^5 + x^3 + x + 1) + x^6 + x^4 + x^3 = x^6 + x^5 + x^4 + x + 1 (mod x^8 + x^5 + x^3 + x + 1)
+        // x^12 = x^11 * x = (x^6 + x^5 + x^4 + x + 1) * x = x^7 + x^6 + x^5 + x^2 + x (mod x^8 + x^5 + x^3 + x + 1)
+        // x^13 = x^12 * x = x^8 + x^7 + x^6 + x^3 + x^2
+        // = (x^5 + x^3 + x + 1) + x^7 + x^6 + x^3 + x^2 = x^7 + x^6 + x^5 + x^2 + x + 1 (mod x^8 + x^5 + x^3 + x + 1)
+        // x^14 = x^13 * x = x^8 + x^7 + x^6 + x^3 + x^2 + x
+        // = (x^5 + x^3 + x + 1) + x^7 + x^6 + x^3 + x^2 + x = x^7 + x^6 + x^5 + x^2 + 1 (mod x^8 + x^5 + x^3 + x + 1)
         //
         // Now having our substitution table
         // x^8 = x^5 + x^3 + x + 1
         // x^9 = x^6 + x^4 + x^2 + x
-        // x^10 = x^7 + x^4 + x^3 + x^2
-        // x^11 = x^4 + 1
-        // x^12 = x^5 + x
-        // x^13 = x^6 + x^2
-        // x^14 = x^7 + x^3
+        // x^10 = x^7 + x^5 + x^3 + x^2
+        // x^11 = x^6 + x^5 + x^4 + x + 1
+        // x^12 = x^7 + x^6 + x^5 + x^2 + x
+        // x^13 = x^7 + x^6 + x^5 + x^2 + x + 1
+        // x^14 = x^7 + x^6 + x^5 + x^2 + 1
         //
         // We can compute our 0xFF * 0xFF (mod 0x12B)
         // = x^14 + x^12 + x^10 + x^8 + x^6 + x^4 + x^2 + 1 (mod x^8 + x^5 + x^3 + x + 1)
-        // = x^7 + x^3 + x^5 + x + x^7 + x^4 + x^3 + x^2 + x^5 + x^3 + x + 1 + x^6 + x^4 + x^2 + 1
-        // = 2 x^7 + x^6 + 2 x^5 + 2 x^4 + 3 x^3 + 2 x^2 + 2 x + 2
+        // = (x^7 + x^6 + x^5 + x^2 + 1) + (x^7 + x^6 + x^5 + x^2 + x) + (x^7 + x^5 + x^3 + x^2)
+        //   + (x^5 + x^3 + x + 1) + x^6 + x^4 + x^2 + 1
+        // = 3 x^7 + 3 x^6 + 4 x^5 + x^4 + 2 x^3 + 4 x^2 + 2 x + 3
         // Reduce Z_2 and we get
-        // x^6 + x^3
+        // x^7 + x^6 + x^4 + 1
 
-        // x^8 = x^5 + x^3 + x + 1
-        if out[8] {
-            out[5] ^= true;
-            out[3] ^= true;
-            out[1] ^= true;
-            out[0] ^= true;
+        for (degree, reduction) in (8..15).zip(Modulus::<M>::REDUCTIONS.iter()) {
+            if out[degree] {
+                for (bit, out) in out.iter_mut().take(8).enumerate() {
+                    *out ^= reduction >> bit & 1 == 1;
+                }
+            }
         }
 
-        // x^9 = x^6 + x^4 + x^2 + x
-        if out[9] {
-            out[6] ^= true;
-            out[4] ^= true;
-            out[2] ^= true;
-            out[1] ^= true;
-        }
-        // x^10 = x^7 + x^4 + x^3 + x^2
-        if out[10] {
-            out[7] ^= true;
-            out[4] ^= true;
-            out[3] ^= true;
-            out[2] ^= true;
-        }
-        // x^11 = x^4 + 1
-        if out[11] {
-            out[4] ^= true;
-            out[0] ^= true;
-        }
-        // x^12 = x^5 + x
-        if out[12] {
-            out[5] ^= true;
-            out[0] ^= true;
+        BiPoly::from_array(&out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{AES_MODULO, ISO_MODULO};
+
+    #[test]
+    fn test_mul() {
+        assert_eq!(BiPoly::<MODULO>(0xFF) * BiPoly(0xFF), BiPoly(0xD1));
+        // x^3 * x^7 = x^10 = x^7 + x^5 + x^3 + x^2
+        assert_eq!(BiPoly::<MODULO>(0x08) * BiPoly(0x80), BiPoly(0xAC));
+        assert_eq!(BiPoly::<MODULO>(0x80) * BiPoly(0x80), BiPoly(0xE5));
+    }
+
+    #[test]
+    fn test_mul_iso() {
+        // x^7 * x = x^8 = x^4 + x^3 + x^2 + 1 (mod 0x11D)
+        assert_eq!(
+            BiPoly::<ISO_MODULO>(0x80) * BiPoly(0x02),
+            BiPoly::<ISO_MODULO>(0x1D)
+        );
+        assert_eq!(
+            BiPoly::<ISO_MODULO>(0x53) * BiPoly(0xCA),
+            BiPoly::<ISO_MODULO>(0x8F)
+        );
+    }
+
+    #[test]
+    fn test_mul_table() {
+        for a in 0..=u8::MAX {
+            for b in 0..=u8::MAX {
+                let expected = BiPoly::<MODULO>(a).mul_bitwise(BiPoly(b));
+                assert_eq!(BiPoly::<MODULO>(a).mul_table(BiPoly(b)), expected);
+                assert_eq!(mul_shift(a, b, MODULO), expected.0);
+
+                let expected = BiPoly::<ISO_MODULO>(a).mul_bitwise(BiPoly(b));
+                assert_eq!(BiPoly::<ISO_MODULO>(a).mul_table(BiPoly(b)), expected);
+                assert_eq!(mul_shift(a, b, ISO_MODULO), expected.0);
+            }
         }
-        // x^13 = x^6 + x^2
-        if out[13] {
-            out[6] ^= true;
-            out[2] ^= true;
+    }
+
+    fn elements<const M: u16>() -> impl Iterator<Item = BiPoly<M>> + Clone {
+        (0..=u8::MAX).map(BiPoly)
+    }
+
+    fn check_field_axioms<const M: u16>() {
+        let zero = BiPoly::<M>::ZERO;
+        let one = BiPoly::<M>::ONE;
+        for a in elements::<M>() {
+            assert_eq!(a + zero, a);
+            assert_eq!(a * one, a);
+            assert_eq!(a * zero, zero);
+            assert_eq!(a + -a, zero);
+            assert_eq!(a + a, zero);
+            assert_eq!(a.pow(0), one);
+            assert_eq!(a.pow(256), a);
+
+            if a == zero {
+                assert_eq!(a.inverse(), None);
+                assert_eq!(a.order(), None);
+                continue;
+            }
+            let inverse = a.inverse().unwrap();
+            assert_eq!(a * inverse, one);
+            assert_eq!(inverse.inverse(), Some(a));
+            assert_eq!(one / a, inverse);
+            let order = a.order().unwrap();
+            assert_eq!(255 % order, 0);
+            assert_eq!(a.pow(order), one);
+
+            for b in elements::<M>() {
+                assert_eq!(a + b, b + a);
+                assert_eq!(a * b, b * a);
+                assert_eq!((a + b) - b, a);
+                assert_eq!(b / a * a, b);
+
+                let mut c = b;
+                c += a;
+                c *= a;
+                assert_eq!(c, (b + a) * a);
+                c -= b * a;
+                c /= a;
+                assert_eq!(c, a);
+            }
         }
-        // x^14 = x^7 + x^3
-        if out[14] {
-            out[7] ^= true;
-            out[3] ^= true;
+
+        // Associativity and distributivity over all triples
+        for a in elements::<M>() {
+            for b in elements::<M>() {
+                let ab = a * b;
+                let a_plus_b = a + b;
+                for c in elements::<M>() {
+                    assert_eq!(ab * c, a * (b * c));
+                    assert_eq!(a_plus_b + c, a + (b + c));
+                    assert_eq!(a * (b + c), ab + a * c);
+                }
+            }
         }
 
-        BiPoly(
-            (u8::from(out[0]) << 0)
-                | (u8::from(out[1]) << 1)
-                | (u8::from(out[2]) << 2)
-                | (u8::from(out[3]) << 3)
-                | (u8::from(out[4]) << 4)
-                | (u8::from(out[5]) << 5)
-                | (u8::from(out[6]) << 6)
-                | (u8::from(out[7]) << 7),
-        )
+        // The elements sum to zero and the non-zero ones multiply to -1, which is one
+        assert_eq!(elements::<M>().sum::<BiPoly<M>>(), zero);
+        assert_eq!(elements::<M>().skip(1).product::<BiPoly<M>>(), one);
+        let all: Vec<BiPoly<M>> = elements().collect();
+        assert_eq!(all.iter().sum::<BiPoly<M>>(), zero);
+        assert_eq!(all[1..].iter().product::<BiPoly<M>>(), one);
+        // The multiplicative group is cyclic: some element has the full order 255
+        assert!(elements::<M>().any(|x| x.order() == Some(255)));
+    }
+
+    #[test]
+    fn test_field_axioms() {
+        check_field_axioms::<MODULO>();
+    }
+
+    #[test]
+    fn test_field_axioms_iso() {
+        check_field_axioms::<ISO_MODULO>();
+    }
+
+    #[test]
+    fn test_field_axioms_aes() {
+        check_field_axioms::<AES_MODULO>();
     }
-}
 
+    #[test]
+    fn test_mul_aes() {
+        // FIPS-197 section 4.2
+        assert_eq!(
+            BiPoly::<AES_MODULO>(0x57) * BiPoly(0x83),
+            BiPoly::<AES_MODULO>(0xC1)
+        );
+        assert_eq!(
+            BiPoly::<AES_MODULO>(0x57) * BiPoly(0x13),
+            BiPoly::<AES_MODULO>(0xFE)
+        );
+        assert_eq!(
+            BiPoly::<AES_MODULO>(0x53).inverse(),
+            Some(BiPoly::<AES_MODULO>(0xCA))
+        );
+    }
+
+    #[test]
+    fn test_irreducible() {
+        assert!(is_irreducible(MODULO));
+        assert!(is_irreducible(ISO_MODULO));
+        assert!(is_irreducible(AES_MODULO));
+        // (x + 1)^8, x^8 and (x^4 + x + 1)^2
+        assert!(!is_irreducible(0x101));
+        assert!(!is_irreducible(0x100));
+        assert!(!is_irreducible(0x111));
+        // Not of degree 8
+        assert!(!is_irreducible(0x13));
+        assert!(!is_irreducible(0x211));
+        assert_eq!((0x100..0x200).filter(|&x| is_irreducible(x)).count(), 30);
+    }
+
+    #[test]
+    fn test_reductions() {
+        // The substitution table worked out in `mul_bitwise`
+        assert_eq!(
+            Modulus::<MODULO>::REDUCTIONS,
+            [0x2B, 0x56, 0xAC, 0x73, 0xE6, 0xE7, 0xE5]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "attempt to divide by zero")]
+    fn test_div_by_zero() {
+        let _ = BiPoly::<MODULO>(1) / BiPoly(0);
+    }
+}
@@ -0,0 +1,290 @@
+use super::bipoly::BiPoly;
+use super::constants::MODULO;
+use super::error::WhirlpoolError;
+use super::matrix::Matrix;
+use super::params::WhirlpoolParams;
+use super::trace::Step;
+
+/// The block cipher W that sits inside the whirlpool compression function, 128-bit for
+/// mini-Whirlpool and 512-bit for the ISO variant. The key goes through the key schedule,
+/// the plaintext through the data path, and both are mixed with the same SB, SC and MR steps.
+pub struct BlockCipher<'a, const N: usize = 4, const M: u16 = MODULO> {
+    params: &'a WhirlpoolParams<N, M>,
+}
+
+impl<'a, const N: usize, const M: u16> BlockCipher<'a, N, M> {
+    pub fn new(params: &'a WhirlpoolParams<N, M>) -> BlockCipher<'a, N, M> {
+        BlockCipher { params }
+    }
+
+    /// Both the key and the plaintext have to be exactly one block long
+    pub fn encrypt_block(&self, key: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, WhirlpoolError> {
+        let ciphertext = encrypt(
+            self.params,
+            Matrix::from_bytes(key)?,
+            Matrix::from_bytes(plaintext)?,
+        );
+        Ok(ciphertext.to_bytes())
+    }
+
+    /// Both the key and the ciphertext have to be exactly one block long
+    pub fn decrypt_block(&self, key: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, WhirlpoolError> {
+        let round_keys = round_keys(self.params, Matrix::from_bytes(key)?);
+        let rounds = self.params.rounds();
+        let mut m = Matrix::from_bytes(ciphertext)?;
+        for (r, k) in round_keys.iter().enumerate().skip(1).rev() {
+            // AK^-1 is AK itself
+            let mix = r < rounds || self.params.final_mix;
+            m = self.inverse_round(m + *k, mix);
+        }
+        Ok((m + round_keys[0]).to_bytes())
+    }
+
+    /// SB^-1 o SC^-1 o MR^-1, without MR^-1 unless `mix` is set
+    fn inverse_round(&self, a: Matrix<N, M>, mix: bool) -> Matrix<N, M> {
+        // MR^-1 inverse mix rows
+        let a_prim = if mix {
+            self.params.layout.mix(self.params.t_inverse, a)
+        } else {
+            a
+        };
+
+        // SC^-1 inverse shift columns and SB^-1 inverse substitute bytes
+        let mut out = Matrix::zeros();
+        for i in 0..N {
+            for j in 0..N {
+                let (row, col) = self.params.layout.shift_source(i, j, N);
+                out.0[row][col] = self.params.sbox.invert(a_prim.0[i][j]);
+            }
+        }
+        out
+    }
+}
+
+/// W_K(P) = (o^R_r=1 RF(K_r)) o AK(K_0) (P)
+/// Where K is the key, K_r is a round key, RF is a round function, AK is an add key step,
+/// MR is a mix rows, SC is a shift columns step, and SB is a substitution bytes step.
+pub(crate) fn encrypt<const N: usize, const M: u16>(
+    params: &WhirlpoolParams<N, M>,
+    key: Matrix<N, M>,
+    plaintext: Matrix<N, M>,
+) -> Matrix<N, M> {
+    encrypt_observed(params, key, plaintext, |_, _, _, _| {})
+}
+
+/// `encrypt` that hands the key and the data state to `observe` after every step, together
+/// with the round number, 0 for the initial AK
+pub(crate) fn encrypt_observed<const N: usize, const M: u16, F>(
+    params: &WhirlpoolParams<N, M>,
+    key: Matrix<N, M>,
+    plaintext: Matrix<N, M>,
+    mut observe: F,
+) -> Matrix<N, M>
+where
+    F: FnMut(usize, Step, &Matrix<N, M>, &Matrix<N, M>),
+{
+    let mut k = key;
+    let schedule = params.key_schedule;
+    let rounds = params.rounds();
+
+    // AK add round key K_0 which is the key itself
+    let mut m = plaintext + key;
+    observe(0, Step::AddKey, &k, &m);
+
+    for (r, constants) in params.round_constants.iter().enumerate() {
+        // The key schedule goes through the same steps as the data, in lockstep. Without it
+        // the key stays K_0 in every round.
+        if schedule {
+            k = sub_bytes(params, k);
+        }
+        m = sub_bytes(params, m);
+        observe(r + 1, Step::SubBytes, &k, &m);
+
+        if schedule {
+            k = shift_columns(params, k);
+        }
+        m = shift_columns(params, m);
+        observe(r + 1, Step::ShiftColumns, &k, &m);
+
+        if schedule {
+            k = params.layout.mix(params.t, k);
+        }
+        if r + 1 < rounds || params.final_mix {
+            m = params.layout.mix(params.t, m);
+        }
+        observe(r + 1, Step::MixRows, &k, &m);
+
+        if schedule {
+            k = add_constants(k, constants);
+        }
+        m = m + k;
+        observe(r + 1, Step::AddKey, &k, &m);
+    }
+    m
+}
+
+/// Key schedule, returns K_0 followed by the round keys K_1..K_R
+fn round_keys<const N: usize, const M: u16>(
+    params: &WhirlpoolParams<N, M>,
+    key: Matrix<N, M>,
+) -> Vec<Matrix<N, M>> {
+    if !params.key_schedule {
+        return vec![key; params.rounds() + 1];
+    }
+    let mut keys = vec![key];
+    for constants in params.round_constants.iter() {
+        let previous = keys[keys.len() - 1];
+        keys.push(add_constants(round(params, previous), constants));
+    }
+    keys
+}
+
+/// AK add key round constant
+fn add_constants<const N: usize, const M: u16>(
+    mut k: Matrix<N, M>,
+    constants: &[BiPoly<M>; N],
+) -> Matrix<N, M> {
+    for (cell, &constant) in k.0[0].iter_mut().zip(constants.iter()) {
+        *cell += constant;
+    }
+    k
+}
+
+/// MR o SC o SB, shared by the key schedule and the data path
+fn round<const N: usize, const M: u16>(
+    params: &WhirlpoolParams<N, M>,
+    a: Matrix<N, M>,
+) -> Matrix<N, M> {
+    params
+        .layout
+        .mix(params.t, shift_columns(params, sub_bytes(params, a)))
+}
+
+/// SB substitute bytes
+fn sub_bytes<const N: usize, const M: u16>(
+    params: &WhirlpoolParams<N, M>,
+    mut a: Matrix<N, M>,
+) -> Matrix<N, M> {
+    for cell in a.0.iter_mut().flatten() {
+        *cell = params.sbox.apply(*cell);
+    }
+    a
+}
+
+/// SC shift columns
+fn shift_columns<const N: usize, const M: u16>(
+    params: &WhirlpoolParams<N, M>,
+    a: Matrix<N, M>,
+) -> Matrix<N, M> {
+    let mut a_prim = Matrix::zeros();
+    for i in 0..N {
+        for j in 0..N {
+            let (row, col) = params.layout.shift_source(i, j, N);
+            a_prim.0[i][j] = a.0[row][col];
+        }
+    }
+    a_prim
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::Reduction;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_encrypt_block() {
+        // W_0(P) + P is the compression of P under the zero chaining value
+        let plaintext = [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D,
+            0x0E, 0x0F,
+        ];
+        let compressed = [
+            0xEA, 0xBC, 0x8C, 0x30, 0x17, 0xDC, 0x2D, 0x09, 0x60, 0x9E, 0x2A, 0x27, 0x2B, 0x26,
+            0x0B, 0xE1,
+        ];
+        let params = WhirlpoolParams::mini();
+        let cipher = BlockCipher::new(&params);
+        let ciphertext = cipher.encrypt_block(&[0u8; 16], &plaintext).unwrap();
+        for i in 0..16 {
+            assert_eq!(ciphertext[i] ^ plaintext[i], compressed[i]);
+        }
+        assert_eq!(
+            cipher.decrypt_block(&[0u8; 16], &ciphertext).unwrap(),
+            plaintext
+        );
+        assert!(matches!(
+            cipher.encrypt_block(&[0u8; 15], &plaintext),
+            Err(WhirlpoolError::InvalidLength {
+                length: 15,
+                expected: 16
+            })
+        ));
+        assert!(cipher.decrypt_block(&[0u8; 16], &ciphertext[1..]).is_err());
+    }
+
+    #[test]
+    fn test_reduced() {
+        let full = WhirlpoolParams::mini();
+        let key: Vec<u8> = (0..16).map(|x| x * 17).collect();
+        let block: Vec<u8> = (0..16).map(|x| x * 3 + 1).collect();
+
+        // One round without MR and key schedule is K + SC(SB(P + K))
+        let params = full
+            .clone()
+            .reduced(Reduction {
+                rounds: Some(1),
+                final_mix: false,
+                key_schedule: false,
+                ..Reduction::default()
+            })
+            .unwrap();
+        let k = Matrix::from_bytes(&key).unwrap();
+        let p = Matrix::from_bytes(&block).unwrap();
+        let expected = shift_columns(&params, sub_bytes(&params, p + k)) + k;
+        let cipher = BlockCipher::new(&params);
+        assert_eq!(
+            cipher.encrypt_block(&key, &block).unwrap(),
+            expected.to_bytes()
+        );
+
+        // Every reduction can still be decrypted
+        for rounds in 1..=full.rounds() {
+            for flags in 0..4 {
+                let params = full
+                    .clone()
+                    .reduced(Reduction {
+                        rounds: Some(rounds),
+                        final_mix: flags & 1 == 0,
+                        key_schedule: flags & 2 == 0,
+                        ..Reduction::default()
+                    })
+                    .unwrap();
+                let cipher = BlockCipher::new(&params);
+                let ciphertext = cipher.encrypt_block(&key, &block).unwrap();
+                assert_eq!(cipher.decrypt_block(&key, &ciphertext).unwrap(), block);
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip(key in any::<[u8; 16]>(), block in any::<[u8; 16]>()) {
+            let params = WhirlpoolParams::mini();
+            let cipher = BlockCipher::new(&params);
+            let ciphertext = cipher.encrypt_block(&key, &block).unwrap();
+            prop_assert_eq!(cipher.decrypt_block(&key, &ciphertext).unwrap(), block);
+            let decrypted = cipher.decrypt_block(&key, &block).unwrap();
+            prop_assert_eq!(cipher.encrypt_block(&key, &decrypted).unwrap(), block);
+        }
+
+        #[test]
+        fn test_round_trip_iso(key in vec(any::<u8>(), 64), block in vec(any::<u8>(), 64)) {
+            let params = WhirlpoolParams::iso();
+            let cipher = BlockCipher::new(&params);
+            let ciphertext = cipher.encrypt_block(&key, &block).unwrap();
+            prop_assert_eq!(cipher.decrypt_block(&key, &ciphertext).unwrap(), block);
+        }
+    }
+}
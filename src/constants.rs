@@ -6,6 +6,14 @@ pub const LENGTH_SIZE: usize = 2;
 
 pub const MODULO: u16 = 0b0000_0001_0010_1011; // x^8 + x^5 + x^3 + x + 1 (0x12B)
 
+// ISO/IEC 10118-3 Whirlpool
+pub const ISO_LENGTH_SIZE: usize = 32;
+pub const ISO_ROUNDS: usize = 10;
+pub const ISO_MODULO: u16 = 0b0000_0001_0001_1101; // x^8 + x^4 + x^3 + x^2 + 1 (0x11D)
+
+// AES, for comparison
+pub const AES_MODULO: u16 = 0b0000_0001_0001_1011; // x^8 + x^4 + x^3 + x + 1 (0x11B)
+
 pub const R: [[BiPoly; 4]; 6] = [
     [BiPoly(0x05), BiPoly(0x8C), BiPoly(0xB5), BiPoly(0x60)],
     [BiPoly(0x31), BiPoly(0x60), BiPoly(0xB2), BiPoly(0xA3)],
@@ -21,6 +29,90 @@ pub const T: Matrix = Matrix([
     [BiPoly(5), BiPoly(3), BiPoly(2), BiPoly(1)],
 ]);
 
+/// Circulant diffusion matrix cir(1, 1, 4, 1, 8, 5, 2, 9) of the ISO Whirlpool
+pub const ISO_C: Matrix<8, ISO_MODULO> = Matrix([
+    [
+        BiPoly(0x01),
+        BiPoly(0x01),
+        BiPoly(0x04),
+        BiPoly(0x01),
+        BiPoly(0x08),
+        BiPoly(0x05),
+        BiPoly(0x02),
+        BiPoly(0x09),
+    ],
+    [
+        BiPoly(0x09),
+        BiPoly(0x01),
+        BiPoly(0x01),
+        BiPoly(0x04),
+        BiPoly(0x01),
+        BiPoly(0x08),
+        BiPoly(0x05),
+        BiPoly(0x02),
+    ],
+    [
+        BiPoly(0x02),
+        BiPoly(0x09),
+        BiPoly(0x01),
+        BiPoly(0x01),
+        BiPoly(0x04),
+        BiPoly(0x01),
+        BiPoly(0x08),
+        BiPoly(0x05),
+    ],
+    [
+        BiPoly(0x05),
+        BiPoly(0x02),
+        BiPoly(0x09),
+        BiPoly(0x01),
+        BiPoly(0x01),
+        BiPoly(0x04),
+        BiPoly(0x01),
+        BiPoly(0x08),
+    ],
+    [
+        BiPoly(0x08),
+        BiPoly(0x05),
+        BiPoly(0x02),
+        BiPoly(0x09),
+        BiPoly(0x01),
+        BiPoly(0x01),
+        BiPoly(0x04),
+        BiPoly(0x01),
+    ],
+    [
+        BiPoly(0x01),
+        BiPoly(0x08),
+        BiPoly(0x05),
+        BiPoly(0x02),
+        BiPoly(0x09),
+        BiPoly(0x01),
+        BiPoly(0x01),
+        BiPoly(0x04),
+    ],
+    [
+        BiPoly(0x04),
+        BiPoly(0x01),
+        BiPoly(0x08),
+        BiPoly(0x05),
+        BiPoly(0x02),
+        BiPoly(0x09),
+        BiPoly(0x01),
+        BiPoly(0x01),
+    ],
+    [
+        BiPoly(0x01),
+        BiPoly(0x04),
+        BiPoly(0x01),
+        BiPoly(0x08),
+        BiPoly(0x05),
+        BiPoly(0x02),
+        BiPoly(0x09),
+        BiPoly(0x01),
+    ],
+]);
+
 pub const MATRIX: [[BiPoly; 16]; 16] = [
     [
         BiPoly(0x34),
@@ -311,3 +403,295 @@ pub const MATRIX: [[BiPoly; 16]; 16] = [
         BiPoly(0x76),
     ],
 ];
+
+/// S-box of the ISO Whirlpool, built from the E, E^-1 and R mini-boxes
+pub const ISO_MATRIX: [[BiPoly<ISO_MODULO>; 16]; 16] = [
+    [
+        BiPoly(0x18),
+        BiPoly(0x23),
+        BiPoly(0xC6),
+        BiPoly(0xE8),
+        BiPoly(0x87),
+        BiPoly(0xB8),
+        BiPoly(0x01),
+        BiPoly(0x4F),
+        BiPoly(0x36),
+        BiPoly(0xA6),
+        BiPoly(0xD2),
+        BiPoly(0xF5),
+        BiPoly(0x79),
+        BiPoly(0x6F),
+        BiPoly(0x91),
+        BiPoly(0x52),
+    ],
+    [
+        BiPoly(0x60),
+        BiPoly(0xBC),
+        BiPoly(0x9B),
+        BiPoly(0x8E),
+        BiPoly(0xA3),
+        BiPoly(0x0C),
+        BiPoly(0x7B),
+        BiPoly(0x35),
+        BiPoly(0x1D),
+        BiPoly(0xE0),
+        BiPoly(0xD7),
+        BiPoly(0xC2),
+        BiPoly(0x2E),
+        BiPoly(0x4B),
+        BiPoly(0xFE),
+        BiPoly(0x57),
+    ],
+    [
+        BiPoly(0x15),
+        BiPoly(0x77),
+        BiPoly(0x37),
+        BiPoly(0xE5),
+        BiPoly(0x9F),
+        BiPoly(0xF0),
+        BiPoly(0x4A),
+        BiPoly(0xDA),
+        BiPoly(0x58),
+        BiPoly(0xC9),
+        BiPoly(0x29),
+        BiPoly(0x0A),
+        BiPoly(0xB1),
+        BiPoly(0xA0),
+        BiPoly(0x6B),
+        BiPoly(0x85),
+    ],
+    [
+        BiPoly(0xBD),
+        BiPoly(0x5D),
+        BiPoly(0x10),
+        BiPoly(0xF4),
+        BiPoly(0xCB),
+        BiPoly(0x3E),
+        BiPoly(0x05),
+        BiPoly(0x67),
+        BiPoly(0xE4),
+        BiPoly(0x27),
+        BiPoly(0x41),
+        BiPoly(0x8B),
+        BiPoly(0xA7),
+        BiPoly(0x7D),
+        BiPoly(0x95),
+        BiPoly(0xD8),
+    ],
+    [
+        BiPoly(0xFB),
+        BiPoly(0xEE),
+        BiPoly(0x7C),
+        BiPoly(0x66),
+        BiPoly(0xDD),
+        BiPoly(0x17),
+        BiPoly(0x47),
+        BiPoly(0x9E),
+        BiPoly(0xCA),
+        BiPoly(0x2D),
+        BiPoly(0xBF),
+        BiPoly(0x07),
+        BiPoly(0xAD),
+        BiPoly(0x5A),
+        BiPoly(0x83),
+        BiPoly(0x33),
+    ],
+    [
+        BiPoly(0x63),
+        BiPoly(0x02),
+        BiPoly(0xAA),
+        BiPoly(0x71),
+        BiPoly(0xC8),
+        BiPoly(0x19),
+        BiPoly(0x49),
+        BiPoly(0xD9),
+        BiPoly(0xF2),
+        BiPoly(0xE3),
+        BiPoly(0x5B),
+        BiPoly(0x88),
+        BiPoly(0x9A),
+        BiPoly(0x26),
+        BiPoly(0x32),
+        BiPoly(0xB0),
+    ],
+    [
+        BiPoly(0xE9),
+        BiPoly(0x0F),
+        BiPoly(0xD5),
+        BiPoly(0x80),
+        BiPoly(0xBE),
+        BiPoly(0xCD),
+        BiPoly(0x34),
+        BiPoly(0x48),
+        BiPoly(0xFF),
+        BiPoly(0x7A),
+        BiPoly(0x90),
+        BiPoly(0x5F),
+        BiPoly(0x20),
+        BiPoly(0x68),
+        BiPoly(0x1A),
+        BiPoly(0xAE),
+    ],
+    [
+        BiPoly(0xB4),
+        BiPoly(0x54),
+        BiPoly(0x93),
+        BiPoly(0x22),
+        BiPoly(0x64),
+        BiPoly(0xF1),
+        BiPoly(0x73),
+        BiPoly(0x12),
+        BiPoly(0x40),
+        BiPoly(0x08),
+        BiPoly(0xC3),
+        BiPoly(0xEC),
+        BiPoly(0xDB),
+        BiPoly(0xA1),
+        BiPoly(0x8D),
+        BiPoly(0x3D),
+    ],
+    [
+        BiPoly(0x97),
+        BiPoly(0x00),
+        BiPoly(0xCF),
+        BiPoly(0x2B),
+        BiPoly(0x76),
+        BiPoly(0x82),
+        BiPoly(0xD6),
+        BiPoly(0x1B),
+        BiPoly(0xB5),
+        BiPoly(0xAF),
+        BiPoly(0x6A),
+        BiPoly(0x50),
+        BiPoly(0x45),
+        BiPoly(0xF3),
+        BiPoly(0x30),
+        BiPoly(0xEF),
+    ],
+    [
+        BiPoly(0x3F),
+        BiPoly(0x55),
+        BiPoly(0xA2),
+        BiPoly(0xEA),
+        BiPoly(0x65),
+        BiPoly(0xBA),
+        BiPoly(0x2F),
+        BiPoly(0xC0),
+        BiPoly(0xDE),
+        BiPoly(0x1C),
+        BiPoly(0xFD),
+        BiPoly(0x4D),
+        BiPoly(0x92),
+        BiPoly(0x75),
+        BiPoly(0x06),
+        BiPoly(0x8A),
+    ],
+    [
+        BiPoly(0xB2),
+        BiPoly(0xE6),
+        BiPoly(0x0E),
+        BiPoly(0x1F),
+        BiPoly(0x62),
+        BiPoly(0xD4),
+        BiPoly(0xA8),
+        BiPoly(0x96),
+        BiPoly(0xF9),
+        BiPoly(0xC5),
+        BiPoly(0x25),
+        BiPoly(0x59),
+        BiPoly(0x84),
+        BiPoly(0x72),
+        BiPoly(0x39),
+        BiPoly(0x4C),
+    ],
+    [
+        BiPoly(0x5E),
+        BiPoly(0x78),
+        BiPoly(0x38),
+        BiPoly(0x8C),
+        BiPoly(0xD1),
+        BiPoly(0xA5),
+        BiPoly(0xE2),
+        BiPoly(0x61),
+        BiPoly(0xB3),
+        BiPoly(0x21),
+        BiPoly(0x9C),
+        BiPoly(0x1E),
+        BiPoly(0x43),
+        BiPoly(0xC7),
+        BiPoly(0xFC),
+        BiPoly(0x04),
+    ],
+    [
+        BiPoly(0x51),
+        BiPoly(0x99),
+        BiPoly(0x6D),
+        BiPoly(0x0D),
+        BiPoly(0xFA),
+        BiPoly(0xDF),
+        BiPoly(0x7E),
+        BiPoly(0x24),
+        BiPoly(0x3B),
+        BiPoly(0xAB),
+        BiPoly(0xCE),
+        BiPoly(0x11),
+        BiPoly(0x8F),
+        BiPoly(0x4E),
+        BiPoly(0xB7),
+        BiPoly(0xEB),
+    ],
+    [
+        BiPoly(0x3C),
+        BiPoly(0x81),
+        BiPoly(0x94),
+        BiPoly(0xF7),
+        BiPoly(0xB9),
+        BiPoly(0x13),
+        BiPoly(0x2C),
+        BiPoly(0xD3),
+        BiPoly(0xE7),
+        BiPoly(0x6E),
+        BiPoly(0xC4),
+        BiPoly(0x03),
+        BiPoly(0x56),
+        BiPoly(0x44),
+        BiPoly(0x7F),
+        BiPoly(0xA9),
+    ],
+    [
+        BiPoly(0x2A),
+        BiPoly(0xBB),
+        BiPoly(0xC1),
+        BiPoly(0x53),
+        BiPoly(0xDC),
+        BiPoly(0x0B),
+        BiPoly(0x9D),
+        BiPoly(0x6C),
+        BiPoly(0x31),
+        BiPoly(0x74),
+        BiPoly(0xF6),
+        BiPoly(0x46),
+        BiPoly(0xAC),
+        BiPoly(0x89),
+        BiPoly(0x14),
+        BiPoly(0xE1),
+    ],
+    [
+        BiPoly(0x16),
+        BiPoly(0x3A),
+        BiPoly(0x69),
+        BiPoly(0x09),
+        BiPoly(0x70),
+        BiPoly(0xB6),
+        BiPoly(0xD0),
+        BiPoly(0xED),
+        BiPoly(0xCC),
+        BiPoly(0x42),
+        BiPoly(0x98),
+        BiPoly(0xA4),
+        BiPoly(0x28),
+        BiPoly(0x5C),
+        BiPoly(0xF8),
+        BiPoly(0x86),
+    ],
+];
@@ -1,271 +1,324 @@
-use std::convert::TryInto;
-
-use super::bipoly::BiPoly;
-use super::constants::*;
+use super::cipher::{encrypt, encrypt_observed};
+use super::constants::MODULO;
+use super::digest::Digest;
+use super::error::WhirlpoolError;
 use super::matrix::Matrix;
+use super::padding::PaddingError;
+use super::params::WhirlpoolParams;
+use super::trace::{BlockTrace, State, Trace};
 
-pub fn hash(mut input: Vec<u8>) -> [u8; 16] {
-    add_padding(&mut input);
-    let hash = input.chunks(16).fold(Matrix::zeros(), |acc, element| {
-        whirlpool(acc, element.try_into().expect("Slice with incorrect size"))
-    });
-    to_array(&hash)
+pub fn hash<const N: usize, const M: u16>(
+    params: &WhirlpoolParams<N, M>,
+    mut input: Vec<u8>,
+) -> Result<Digest, WhirlpoolError> {
+    add_padding(params, &mut input)?;
+    let hash = input
+        .chunks(params.block_size())
+        .fold(Matrix::zeros(), |acc, element| {
+            whirlpool(params, acc, element)
+        });
+    Ok(Digest::from(hash.to_bytes()))
 }
 
-/// W(K) = (o^10_r=1 RF(K_r)) o AK(K_0)
-/// Where W is the whirlpool function, K is the key input, K_r is a round key
-/// RF is a round function, K_r is a round key, AK is an add key step, MR is a mix rows,
-/// SC is a shift columns step, and SB is a substitution bytes step.
-fn whirlpool(h: Matrix, w: [u8; 16]) -> Matrix {
-    let a = Matrix([
-        [
-            BiPoly(w[0 + 4 * 0]),
-            BiPoly(w[1 + 4 * 0]),
-            BiPoly(w[2 + 4 * 0]),
-            BiPoly(w[3 + 4 * 0]),
-        ],
-        [
-            BiPoly(w[0 + 4 * 1]),
-            BiPoly(w[1 + 4 * 1]),
-            BiPoly(w[2 + 4 * 1]),
-            BiPoly(w[3 + 4 * 1]),
-        ],
-        [
-            BiPoly(w[0 + 4 * 2]),
-            BiPoly(w[1 + 4 * 2]),
-            BiPoly(w[2 + 4 * 2]),
-            BiPoly(w[3 + 4 * 2]),
-        ],
-        [
-            BiPoly(w[0 + 4 * 3]),
-            BiPoly(w[1 + 4 * 3]),
-            BiPoly(w[2 + 4 * 3]),
-            BiPoly(w[3 + 4 * 3]),
-        ],
-    ]);
-    let mut k = h;
+/// [`hash`] that also records the key and the data state after every step of every round
+/// of every block, for comparing intermediate values with a reference implementation
+pub fn hash_traced<const N: usize, const M: u16>(
+    params: &WhirlpoolParams<N, M>,
+    mut input: Vec<u8>,
+) -> Result<Trace<N, M>, WhirlpoolError> {
+    add_padding(params, &mut input)?;
+    let mut h = Matrix::zeros();
+    let mut blocks = Vec::new();
+    for w in input.chunks(params.block_size()) {
+        let a = Matrix::from_block(w);
+        let mut states = Vec::new();
+        let encrypted = encrypt_observed(params, h, a, |round, step, key, data| {
+            states.push(State {
+                round,
+                step,
+                key: *key,
+                data: *data,
+            })
+        });
+        let output = feed_forward(params, h, a, encrypted);
+        blocks.push(BlockTrace {
+            chaining: h,
+            message: a,
+            states,
+            output,
+        });
+        h = output;
+    }
+    Ok(Trace {
+        blocks,
+        digest: Digest::from(h.to_bytes()),
+    })
+}
 
-    // AK add round key K_0 which is
-    let mut m = a + h;
+/// Incremental counterpart of [`hash`]. Message bytes are compressed as soon as a full
+/// block is available, so only the chaining value and one partial block are kept in memory.
+pub struct Hasher<'a, const N: usize = 4, const M: u16 = MODULO> {
+    params: &'a WhirlpoolParams<N, M>,
+    state: Matrix<N, M>,
+    buffer: Vec<u8>,
+    length: usize,
+}
 
-    for round in 0..6 {
-        // SB substitute bytes
-        for i in 0..4 {
-            for j in 0..4 {
-                k.0[i][j] = s(k.0[i][j]);
-                m.0[i][j] = s(m.0[i][j]);
-            }
+impl<'a, const N: usize, const M: u16> Hasher<'a, N, M> {
+    pub fn new(params: &'a WhirlpoolParams<N, M>) -> Hasher<'a, N, M> {
+        Hasher {
+            params,
+            state: Matrix::zeros(),
+            buffer: Vec::with_capacity(params.block_size()),
+            length: 0,
         }
-        // SC shift columns
-        let mut k_prim = Matrix::zeros();
-        let mut m_prim = Matrix::zeros();
-        for i in 0..4 {
-            for j in 0..4 {
-                k_prim.0[i][j] = k.0[i][(j + i) % 4];
-                m_prim.0[i][j] = m.0[i][(j + i) % 4];
+    }
+
+    /// Fails as soon as the message gets longer than the padding allows, without hashing
+    /// `data`, so that a long stream does not have to be read to the end first
+    pub fn update(&mut self, mut data: &[u8]) -> Result<(), WhirlpoolError> {
+        let length = self.length.saturating_add(data.len());
+        if let Some(max_length) = self.params.padding.max_length() {
+            if length as u128 > max_length {
+                return Err(PaddingError::MessageTooLong { length, max_length }.into());
             }
         }
+        self.length = length;
 
-        // MR mix rows
-        k = T * k_prim;
-        m = T * m_prim;
+        let block_size = self.params.block_size();
+        if !self.buffer.is_empty() {
+            let taken = (block_size - self.buffer.len()).min(data.len());
+            self.buffer.extend_from_slice(&data[..taken]);
+            data = &data[taken..];
+            if self.buffer.len() < block_size {
+                return Ok(());
+            }
+            self.state = whirlpool(self.params, self.state, &self.buffer);
+            self.buffer.clear();
+        }
 
-        // AK add key round constant
-        for i in 0..4 {
-            k.0[0][i] = k.0[0][i] + R[round][i];
+        let mut blocks = data.chunks_exact(block_size);
+        for block in &mut blocks {
+            self.state = whirlpool(self.params, self.state, block);
         }
-        m = m + k;
+        self.buffer.extend_from_slice(blocks.remainder());
+        Ok(())
     }
-    m + a + h
-}
 
-fn s(a: BiPoly) -> BiPoly {
-    let row = (a.0 >> 4) as usize;
-    let col = (a.0 & 0b0000_1111) as usize;
-    return MATRIX[row][col];
+    pub fn finalize(mut self) -> Result<Digest, WhirlpoolError> {
+        self.params
+            .padding
+            .pad_tail(&mut self.buffer, self.length, self.params.block_size())?;
+        let hash = self
+            .buffer
+            .chunks(self.params.block_size())
+            .fold(self.state, |acc, element| {
+                whirlpool(self.params, acc, element)
+            });
+        Ok(Digest::from(hash.to_bytes()))
+    }
 }
 
-fn to_array(matrix: &Matrix) -> [u8; 16] {
-    let flattened: Vec<u8> = matrix.0.iter().flatten().map(|x| x.0).collect();
-    flattened.try_into().expect("Could not map vec to array")
+/// Miyaguchi-Preneel compression H_i = W_{H_{i-1}}(m_i) + m_i + H_{i-1}
+/// Where W is the whirlpool block cipher keyed with the chaining value H_{i-1}.
+fn whirlpool<const N: usize, const M: u16>(
+    params: &WhirlpoolParams<N, M>,
+    h: Matrix<N, M>,
+    w: &[u8],
+) -> Matrix<N, M> {
+    let a = Matrix::from_block(w);
+    feed_forward(params, h, a, encrypt(params, h, a))
 }
 
-fn add_padding(input: &mut Vec<u8>) {
-    let payload_size = input.len();
-    let blocks = (payload_size + LENGTH_SIZE - 1) / BLOCK_SIZE + 1;
-    let bytes = blocks * BLOCK_SIZE;
-    input.resize(bytes, 0u8);
-    let elem_lower = input.get_mut(bytes - 1).unwrap();
-    if payload_size <= (u8::MAX as usize) {
-        *elem_lower = payload_size as u8;
+/// Adds the message block and the chaining value to the cipher output, unless the params
+/// leave the feed-forward out
+fn feed_forward<const N: usize, const M: u16>(
+    params: &WhirlpoolParams<N, M>,
+    h: Matrix<N, M>,
+    a: Matrix<N, M>,
+    encrypted: Matrix<N, M>,
+) -> Matrix<N, M> {
+    if params.feed_forward {
+        encrypted + a + h
     } else {
-        let elem_upper = input.get_mut((bytes - 2)..=(bytes - 1)).unwrap();
-        elem_upper[0] = (((payload_size as u16) & 0xFF_00) >> 8) as u8;
-        elem_upper[1] = ((payload_size as u16) & 0x00_FF) as u8;
+        encrypted
     }
 }
 
+fn add_padding<const N: usize, const M: u16>(
+    params: &WhirlpoolParams<N, M>,
+    input: &mut Vec<u8>,
+) -> Result<(), PaddingError> {
+    params.padding.pad(input, params.block_size())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::convert::TryInto;
+    use crate::cipher::BlockCipher;
+    use crate::params::Reduction;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use proptest::sample::Index;
 
     #[test]
     fn test_whirpool() {
+        let params = WhirlpoolParams::mini();
         {
             let input = [
                 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D,
                 0x0E, 0x0F,
             ];
-            let hash = whirlpool(Matrix::zeros(), input);
+            let hash = whirlpool(&params, Matrix::zeros(), &input);
             let expected = [
                 0xEA, 0xBC, 0x8C, 0x30, 0x17, 0xDC, 0x2D, 0x09, 0x60, 0x9E, 0x2A, 0x27, 0x2B, 0x26,
                 0x0B, 0xE1,
             ];
-            assert_eq!(to_array(&hash), expected);
+            assert_eq!(hash.to_bytes(), expected);
         }
         {
             let input = [0u8; 16];
-            let hash = whirlpool(Matrix::zeros(), input);
+            let hash = whirlpool(&params, Matrix::zeros(), &input);
             let expected = [
                 0xF4, 0xE7, 0xC2, 0x07, 0x92, 0xAF, 0x80, 0x9B, 0x01, 0x84, 0xC6, 0x84, 0x7B, 0xAF,
                 0xE8, 0x6A,
             ];
-            assert_eq!(to_array(&hash), expected);
+            assert_eq!(hash.to_bytes(), expected);
         }
         {
-            let mut input = &mut "AbCxYz".as_bytes().to_vec();
+            let input = &mut "AbCxYz".as_bytes().to_vec();
             println!("input {:?}", input);
-            add_padding(&mut input);
+            add_padding(&params, input).unwrap();
             println!("input after padding {:?}", input);
             let hash = input.chunks(16).fold(Matrix::zeros(), |acc, element| {
-                whirlpool(acc, element.try_into().expect("Slice with incorrect size"))
+                whirlpool(&params, acc, element)
             });
             let expected = [
                 0x67, 0x2F, 0xAE, 0x13, 0xF4, 0x8D, 0xED, 0xA1, 0x99, 0x91, 0x31, 0x9E, 0x06, 0xFF,
                 0xC7, 0x88,
             ];
-            assert_eq!(to_array(&hash), expected);
+            assert_eq!(hash.to_bytes(), expected);
         }
         {
-            let mut input = &mut "1234567890".as_bytes().to_vec();
-            add_padding(&mut input);
+            let input = &mut "1234567890".as_bytes().to_vec();
+            add_padding(&params, input).unwrap();
             let hash = input.chunks(16).fold(Matrix::zeros(), |acc, element| {
-                whirlpool(acc, element.try_into().expect("Slice with incorrect size"))
+                whirlpool(&params, acc, element)
             });
             let expected = [
                 0x8E, 0x65, 0x6F, 0xBC, 0xB4, 0xA3, 0xDF, 0xC4, 0xA1, 0x5F, 0x96, 0x90, 0xD2, 0xCC,
                 0x12, 0x63,
             ];
-            assert_eq!(to_array(&hash), expected);
+            assert_eq!(hash.to_bytes(), expected);
         }
         {
-            let mut input = &mut "Ala ma kota, kot ma ale.".as_bytes().to_vec();
-            add_padding(&mut input);
+            let input = &mut "Ala ma kota, kot ma ale.".as_bytes().to_vec();
+            add_padding(&params, input).unwrap();
             let hash = input.chunks(16).fold(Matrix::zeros(), |acc, element| {
-                whirlpool(acc, element.try_into().expect("Slice with incorrect size"))
+                whirlpool(&params, acc, element)
             });
             let expected = [
                 0x83, 0xA9, 0xFB, 0x7E, 0x22, 0x64, 0xAE, 0x75, 0x65, 0x36, 0xB5, 0x1A, 0xA5, 0xDD,
                 0x4E, 0x51,
             ];
-            assert_eq!(to_array(&hash), expected);
+            assert_eq!(hash.to_bytes(), expected);
         }
         {
-            let mut input = &mut "Ty, ktory wchodzisz, zegnaj sie z nadzieja."
+            let input = &mut "Ty, ktory wchodzisz, zegnaj sie z nadzieja."
                 .as_bytes()
                 .to_vec();
-            add_padding(&mut input);
+            add_padding(&params, input).unwrap();
             let hash = input.chunks(16).fold(Matrix::zeros(), |acc, element| {
-                whirlpool(acc, element.try_into().expect("Slice with incorrect size"))
+                whirlpool(&params, acc, element)
             });
             let expected = [
                 0x2B, 0xE5, 0xCC, 0x98, 0xDC, 0xC9, 0x24, 0xC8, 0x66, 0xED, 0xCF, 0xF9, 0xD1, 0x1A,
                 0x75, 0xFB,
             ];
-            assert_eq!(to_array(&hash), expected);
+            assert_eq!(hash.to_bytes(), expected);
         }
         {
-            let mut input = &mut "Litwo, Ojczyzno moja! ty jestes jak zdrowie;"
+            let input = &mut "Litwo, Ojczyzno moja! ty jestes jak zdrowie;"
                 .as_bytes()
                 .to_vec();
-            add_padding(&mut input);
+            add_padding(&params, input).unwrap();
             let hash = input.chunks(16).fold(Matrix::zeros(), |acc, element| {
-                whirlpool(acc, element.try_into().expect("Slice with incorrect size"))
+                whirlpool(&params, acc, element)
             });
             let expected = [
                 0xCC, 0xE8, 0x5A, 0x43, 0x1C, 0x3C, 0x2D, 0x8F, 0xC1, 0x02, 0xE4, 0x99, 0x3D, 0xFB,
                 0xD3, 0x33,
             ];
-            assert_eq!(to_array(&hash), expected);
+            assert_eq!(hash.to_bytes(), expected);
         }
         {
-            let mut input = &mut "a".repeat(48000).as_bytes().to_vec();
-            add_padding(&mut input);
+            let input = &mut "a".repeat(48000).as_bytes().to_vec();
+            add_padding(&params, input).unwrap();
             let hash = input
                 .chunks(16)
                 .enumerate()
                 .fold(Matrix::zeros(), |acc, (i, element)| {
                     println!("folding {}% {}/{}", 100 * i / (48000 / 16), i, 48000 / 16);
-                    whirlpool(acc, element.try_into().expect("Slice with incorrect size"))
+                    whirlpool(&params, acc, element)
                 });
             let expected = [
                 0x4A, 0x07, 0x19, 0x09, 0xC7, 0xA6, 0xBD, 0x41, 0x5B, 0xB8, 0xA2, 0x41, 0x87, 0xB3,
                 0x61, 0xEB,
             ];
-            assert_eq!(to_array(&hash), expected);
+            assert_eq!(hash.to_bytes(), expected);
         }
         {
-            let mut input = &mut "a".repeat(48479).as_bytes().to_vec();
-            add_padding(&mut input);
+            let input = &mut "a".repeat(48479).as_bytes().to_vec();
+            add_padding(&params, input).unwrap();
             let hash = input
                 .chunks(16)
                 .enumerate()
                 .fold(Matrix::zeros(), |acc, (i, element)| {
                     println!("folding {}% {}/{}", 100 * i / (48000 / 16), i, 48000 / 16);
-                    whirlpool(acc, element.try_into().expect("Slice with incorrect size"))
+                    whirlpool(&params, acc, element)
                 });
             let expected = [
                 0x7C, 0x93, 0x0B, 0x4F, 0xEE, 0x8D, 0x0A, 0x5F, 0x12, 0xE3, 0x81, 0x74, 0x74, 0x6B,
                 0x28, 0xBE,
             ];
-            assert_eq!(to_array(&hash), expected);
+            assert_eq!(hash.to_bytes(), expected);
         }
         {
-            let mut input = &mut "a".repeat(48958).as_bytes().to_vec();
-            add_padding(&mut input);
+            let input = &mut "a".repeat(48958).as_bytes().to_vec();
+            add_padding(&params, input).unwrap();
             let hash = input
                 .chunks(16)
                 .enumerate()
                 .fold(Matrix::zeros(), |acc, (i, element)| {
                     println!("folding {}% {}/{}", 100 * i / (48000 / 16), i, 48000 / 16);
-                    whirlpool(acc, element.try_into().expect("Slice with incorrect size"))
+                    whirlpool(&params, acc, element)
                 });
             let expected = [
                 0x4D, 0xB0, 0x06, 0x4A, 0x8A, 0xE7, 0xE7, 0x8D, 0xDC, 0x0C, 0xC4, 0xD9, 0xD6, 0x91,
                 0xEE, 0xAE,
             ];
-            assert_eq!(to_array(&hash), expected);
+            assert_eq!(hash.to_bytes(), expected);
         }
     }
 
     #[test]
     fn test_padding() {
+        let params = WhirlpoolParams::mini();
         {
             let mut input = vec![0, 1, 2, 3, 4, 5];
-            add_padding(&mut input);
+            add_padding(&params, &mut input).unwrap();
             assert_eq!(input.len(), 16);
             assert_eq!(input, vec![0, 1, 2, 3, 4, 5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 6]);
         }
         {
             let mut input = vec![0, 1, 2, 3, 4, 5, 6];
-            add_padding(&mut input);
+            add_padding(&params, &mut input).unwrap();
             assert_eq!(input.len(), 16);
             assert_eq!(input, vec![0, 1, 2, 3, 4, 5, 6, 0, 0, 0, 0, 0, 0, 0, 0, 7]);
         }
         {
             let mut input = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13];
-            add_padding(&mut input);
+            add_padding(&params, &mut input).unwrap();
             assert_eq!(input.len(), 16);
             assert_eq!(
                 input,
@@ -274,7 +327,7 @@ mod tests {
         }
         {
             let mut input = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
-            add_padding(&mut input);
+            add_padding(&params, &mut input).unwrap();
             assert_eq!(input.len(), 32);
             assert_eq!(
                 input,
@@ -285,4 +338,128 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_hasher() {
+        let input = "Ty, ktory wchodzisz, zegnaj sie z nadzieja.".as_bytes();
+        let params = WhirlpoolParams::mini();
+        let mut hasher = Hasher::new(&params);
+        input
+            .iter()
+            .for_each(|byte| hasher.update(&[*byte]).unwrap());
+        let expected = [
+            0x2B, 0xE5, 0xCC, 0x98, 0xDC, 0xC9, 0x24, 0xC8, 0x66, 0xED, 0xCF, 0xF9, 0xD1, 0x1A,
+            0x75, 0xFB,
+        ];
+        assert_eq!(hasher.finalize().unwrap().as_ref(), expected);
+        assert_eq!(
+            Hasher::new(&params).finalize().unwrap(),
+            hash(&params, Vec::new()).unwrap()
+        );
+
+        // The length is checked on every update, not only when finalizing
+        let mut hasher = Hasher::new(&params);
+        hasher.update(&[0; 65535]).unwrap();
+        assert!(matches!(
+            hasher.update(&[0]),
+            Err(WhirlpoolError::Padding(PaddingError::MessageTooLong {
+                length: 65536,
+                max_length: 65535
+            }))
+        ));
+        let params = params
+            .with_padding(crate::padding::Padding::Strengthened { size: 8 })
+            .unwrap();
+        let mut hasher = Hasher::new(&params);
+        hasher.update(&[0; 65536]).unwrap();
+        assert_eq!(
+            hasher.finalize().unwrap(),
+            hash(&params, vec![0; 65536]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_reduced() {
+        let input = [7u8; 16];
+        let params = WhirlpoolParams::mini()
+            .reduced(Reduction {
+                rounds: Some(3),
+                feed_forward: false,
+                ..Reduction::default()
+            })
+            .unwrap();
+        let encrypted = BlockCipher::new(&params)
+            .encrypt_block(&[0; 16], &input)
+            .unwrap();
+        assert_eq!(
+            whirlpool(&params, Matrix::zeros(), &input).to_bytes(),
+            encrypted
+        );
+
+        // Fewer rounds change the digest, all of them give the full hash back
+        let reduce = |rounds| {
+            let params = WhirlpoolParams::mini()
+                .reduced(Reduction {
+                    rounds: Some(rounds),
+                    ..Reduction::default()
+                })
+                .unwrap();
+            hash(&params, b"abc".to_vec()).unwrap()
+        };
+        let full = hash(&WhirlpoolParams::mini(), b"abc".to_vec()).unwrap();
+        assert_eq!(reduce(6), full);
+        assert_ne!(reduce(5), full);
+        assert_ne!(reduce(1), reduce(2));
+    }
+
+    #[test]
+    fn test_iso_whirlpool() {
+        // NESSIE test vectors of the final Whirlpool
+        let params = WhirlpoolParams::iso();
+        let vectors = [
+            ("", "19FA61D75522A4669B44E39C1D2E1726C530232130D407F89AFEE0964997F7A73E83BE698B288FEBCF88E3E03C4F0757EA8964E59B63D93708B138CC42A66EB3"),
+            ("a", "8ACA2602792AEC6F11A67206531FB7D7F0DFF59413145E6973C45001D0087B42D11BC645413AEFF63A42391A39145A591A92200D560195E53B478584FDAE231A"),
+            ("abc", "4E2448A4C6F486BB16B6562C73B4020BF3043E3A731BCE721AE1B303D97E6D4C7181EEBDB6C57E277D0E34957114CBD6C797FC9D95D8B582D225292076D4EEF5"),
+            ("message digest", "378C84A4126E2DC6E56DCC7458377AAC838D00032230F53CE1F5700C0FFB4D3B8421557659EF55C106B4B52AC5A4AAA692ED920052838F3362E86DBD37A8903E"),
+            ("abcdefghijklmnopqrstuvwxyz", "F1D754662636FFE92C82EBB9212A484A8D38631EAD4238F5442EE13B8054E41B08BF2A9251C30B6A0B8AAE86177AB4A6F68F673E7207865D5D9819A3DBA4EB3B"),
+            ("ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789", "DC37E008CF9EE69BF11F00ED9ABA26901DD7C28CDEC066CC6AF42E40F82F3A1E08EBA26629129D8FB7CB57211B9281A65517CC879D7B962142C65F5A7AF01467"),
+            ("12345678901234567890123456789012345678901234567890123456789012345678901234567890", "466EF18BABB0154D25B9D38A6414F5C08784372BCCB204D6549C4AFADB6014294D5BD8DF2A6C44E538CD047B2681A51A2C60481E88C5A20B2C2A80CF3A9A083B"),
+            ("abcdbcdecdefdefgefghfghighijhijk", "2A987EA40F917061F5D6F0A0E4644F488A7A5A52DEEE656207C562F988E95C6916BDC8031BC5BE1B7B947639FE050B56939BAAA0ADFF9AE6745B7B181C3BE3FD"),
+        ];
+        for (input, expected) in vectors.iter() {
+            let digest = hash(&params, input.as_bytes().to_vec()).unwrap();
+            assert_eq!(&format!("{:X}", digest), expected);
+        }
+    }
+
+    fn hash_in_chunks<const N: usize, const M: u16>(
+        params: &WhirlpoolParams<N, M>,
+        input: &[u8],
+        splits: &[Index],
+    ) -> Result<Digest, WhirlpoolError> {
+        let mut splits: Vec<usize> = splits.iter().map(|x| x.index(input.len() + 1)).collect();
+        splits.sort_unstable();
+
+        let mut hasher = Hasher::new(params);
+        let mut start = 0;
+        for end in splits.into_iter().chain(std::iter::once(input.len())) {
+            hasher.update(&input[start..end])?;
+            start = end;
+        }
+        hasher.finalize()
+    }
+
+    proptest! {
+        #[test]
+        fn test_hasher_matches_hash(input in vec(any::<u8>(), 0..300), splits in vec(any::<Index>(), 0..8)) {
+            let params = WhirlpoolParams::mini();
+            prop_assert_eq!(hash_in_chunks(&params, &input, &splits).unwrap(), hash(&params, input).unwrap());
+        }
+
+        #[test]
+        fn test_iso_hasher_matches_hash(input in vec(any::<u8>(), 0..300), splits in vec(any::<Index>(), 0..8)) {
+            let params = WhirlpoolParams::iso();
+            prop_assert_eq!(hash_in_chunks(&params, &input, &splits).unwrap(), hash(&params, input).unwrap());
+        }
+    }
 }
@@ -0,0 +1,253 @@
+use super::bipoly::BiPoly;
+use super::matrix::Matrix;
+
+/// Rows and columns of a square submatrix, in increasing order
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Submatrix {
+    pub rows: Vec<usize>,
+    pub cols: Vec<usize>,
+}
+
+impl std::fmt::Display for Submatrix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "rows {:?} columns {:?}", self.rows, self.cols)
+    }
+}
+
+/// How well a diffusion matrix spreads differences and linear masks over the state
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Report {
+    /// Smallest number of non-zero bytes in an input difference and its output, N + 1 at best
+    pub differential_branch_number: usize,
+    /// The same for linear masks, which go through the transposed matrix
+    pub linear_branch_number: usize,
+    /// Square submatrices without an inverse, the matrix is MDS when there are none
+    pub singular: Vec<Submatrix>,
+}
+
+impl Report {
+    pub fn is_mds(&self) -> bool {
+        self.singular.is_empty()
+    }
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "differential branch number: {}",
+            self.differential_branch_number
+        )?;
+        writeln!(f, "linear branch number: {}", self.linear_branch_number)?;
+        if self.is_mds() {
+            writeln!(f, "MDS: every square submatrix is non-singular")
+        } else {
+            writeln!(f, "not MDS, {} singular submatrices:", self.singular.len())?;
+            for submatrix in self.singular.iter() {
+                writeln!(f, "    {}", submatrix)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+pub fn analyze<const N: usize, const M: u16>(matrix: &Matrix<N, M>) -> Report {
+    Report {
+        differential_branch_number: branch_number(matrix),
+        linear_branch_number: branch_number(&matrix.transpose()),
+        singular: singular_submatrices(matrix),
+    }
+}
+
+/// A matrix is MDS (maximum distance separable) when every square submatrix is non-singular,
+/// which is the same as having the largest possible branch number N + 1
+pub fn is_mds<const N: usize, const M: u16>(matrix: &Matrix<N, M>) -> bool {
+    subsets(N).all(|rows| {
+        subsets(N)
+            .filter(|cols| cols.len() == rows.len())
+            .all(|cols| rank(matrix, &rows, &cols) == rows.len())
+    })
+}
+
+/// Every singular square submatrix, smallest first
+pub fn singular_submatrices<const N: usize, const M: u16>(matrix: &Matrix<N, M>) -> Vec<Submatrix> {
+    let mut singular = Vec::new();
+    for size in 1..=N {
+        for rows in subsets(N).filter(|x| x.len() == size) {
+            for cols in subsets(N).filter(|x| x.len() == size) {
+                if rank(matrix, &rows, &cols) < size {
+                    singular.push(Submatrix {
+                        rows: rows.clone(),
+                        cols,
+                    });
+                }
+            }
+        }
+    }
+    singular
+}
+
+/// The smallest `wt(a) + wt(matrix * a)` over all non-zero column vectors `a`, where `wt`
+/// counts the non-zero bytes.
+///
+/// Trying all 256^N inputs is out of the question, instead this looks at input supports S
+/// and sets Z of output bytes that should be zero. Some non-zero `a` inside S maps to zero
+/// on Z exactly when the rows Z and columns S of the matrix have a rank below |S|, and then
+/// `wt(a) + wt(matrix * a)` is at most |S| + N - |Z|. Every input reaches its own bound, so
+/// the smallest bound is the branch number.
+pub fn branch_number<const N: usize, const M: u16>(matrix: &Matrix<N, M>) -> usize {
+    let mut best = 2 * N;
+    for cols in subsets(N).filter(|x| !x.is_empty()) {
+        for rows in subsets(N) {
+            let weight = cols.len() + N - rows.len();
+            if weight < best && rank(matrix, &rows, &cols) < cols.len() {
+                best = weight;
+            }
+        }
+    }
+    best
+}
+
+/// Every first row with entries from `candidates` whose circulant matrix is MDS modulo `M`
+pub fn mds_circulants<const N: usize, const M: u16>(
+    candidates: &[BiPoly<M>],
+) -> Vec<[BiPoly<M>; N]> {
+    let mut found = Vec::new();
+    let mut digits = [0usize; N];
+    if candidates.is_empty() {
+        return found;
+    }
+    loop {
+        let mut first_row = [BiPoly(0); N];
+        for (cell, &digit) in first_row.iter_mut().zip(digits.iter()) {
+            *cell = candidates[digit];
+        }
+        if is_mds(&Matrix::circulant(first_row)) {
+            found.push(first_row);
+        }
+
+        // Counts through all choices, the last position changing fastest
+        match (0..N).rev().find(|&i| digits[i] + 1 < candidates.len()) {
+            Some(i) => {
+                digits[i] += 1;
+                for digit in digits.iter_mut().skip(i + 1) {
+                    *digit = 0;
+                }
+            }
+            None => return found,
+        }
+    }
+}
+
+/// All subsets of `0..n` as sorted index lists
+fn subsets(n: usize) -> impl Iterator<Item = Vec<usize>> {
+    assert!(n < 32, "Too many rows");
+    (0u32..1 << n).map(move |mask| (0..n).filter(|i| mask >> i & 1 == 1).collect())
+}
+
+/// Rank of the submatrix at `rows` and `cols`, by Gaussian elimination
+fn rank<const N: usize, const M: u16>(
+    matrix: &Matrix<N, M>,
+    rows: &[usize],
+    cols: &[usize],
+) -> usize {
+    let mut a: Vec<Vec<BiPoly<M>>> = rows
+        .iter()
+        .map(|&i| cols.iter().map(|&j| matrix.0[i][j]).collect())
+        .collect();
+    let mut rank = 0;
+    for col in 0..cols.len() {
+        let pivot = match (rank..a.len()).find(|&row| a[row][col] != BiPoly(0)) {
+            Some(pivot) => pivot,
+            None => continue,
+        };
+        a.swap(rank, pivot);
+        let pivot_row = a[rank].clone();
+        for row in a.iter_mut().skip(rank + 1) {
+            let factor = row[col] / pivot_row[col];
+            for (cell, &x) in row.iter_mut().zip(pivot_row.iter()).skip(col) {
+                *cell -= factor * x;
+            }
+        }
+        rank += 1;
+    }
+    rank
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{AES_MODULO, ISO_C, ISO_MODULO, MODULO, T};
+
+    #[test]
+    fn test_mini() {
+        let report = analyze(&T);
+        assert!(report.is_mds());
+        assert!(is_mds(&T));
+        assert_eq!(report.differential_branch_number, 5);
+        assert_eq!(report.linear_branch_number, 5);
+    }
+
+    #[test]
+    fn test_iso_and_aes() {
+        assert!(is_mds(&ISO_C));
+        assert_eq!(branch_number(&ISO_C), 9);
+
+        // AES MixColumns
+        let mix_columns =
+            Matrix::<4, AES_MODULO>::circulant([BiPoly(2), BiPoly(3), BiPoly(1), BiPoly(1)]);
+        assert!(is_mds(&mix_columns));
+        assert_eq!(branch_number(&mix_columns), 5);
+    }
+
+    #[test]
+    fn test_not_mds() {
+        // With all entries equal every 2x2 submatrix has two equal rows
+        let ones = Matrix::<4>::circulant([BiPoly(1); 4]);
+        let report = analyze(&ones);
+        assert!(!report.is_mds());
+        // Every 2x2 and larger submatrix is singular, the 1x1 ones are not
+        assert_eq!(report.singular.len(), 36 + 16 + 1);
+        assert_eq!(
+            report.singular[0],
+            Submatrix {
+                rows: vec![0, 1],
+                cols: vec![0, 1]
+            }
+        );
+        // (1, 1, 0, 0) is mapped to zero
+        assert_eq!(report.differential_branch_number, 2);
+
+        let identity = Matrix::<4>::identity();
+        assert_eq!(branch_number(&identity), 2);
+        assert_eq!(singular_submatrices(&identity).len(), 12 + 30 + 12);
+        assert!(report.to_string().contains("rows [0, 1] columns [0, 1]"));
+    }
+
+    #[test]
+    fn test_branch_number_bound() {
+        // The bound found from the ranks is met by an actual input
+        let ones = Matrix::<4>::circulant([BiPoly(1), BiPoly(1), BiPoly(0), BiPoly(0)]);
+        let best = (1..=u8::MAX)
+            .flat_map(|a| (0..=u8::MAX).map(move |b| [a, b, 0, 0]))
+            .map(|input| {
+                let v = crate::matrix::Vector(input.map(BiPoly));
+                let out = ones * v;
+                input.iter().filter(|&&x| x != 0).count()
+                    + out.0.iter().filter(|&&x| x != BiPoly(0)).count()
+            })
+            .min()
+            .unwrap();
+        assert_eq!(branch_number(&ones), best);
+    }
+
+    #[test]
+    fn test_mds_circulants() {
+        let candidates: Vec<BiPoly<MODULO>> = (1..=8).map(BiPoly).collect();
+        let found = mds_circulants::<4, MODULO>(&candidates);
+        assert!(found.contains(&T.0[0]));
+        assert!(!found.contains(&[BiPoly(1); 4]));
+        assert!(found.iter().all(|&x| is_mds(&Matrix::circulant(x))));
+        assert!(mds_circulants::<4, ISO_MODULO>(&[]).is_empty());
+    }
+}
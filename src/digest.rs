@@ -0,0 +1,137 @@
+use super::encoding::{from_hex, to_base64, to_hex, HexError};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+/// The output of a hash: 16 bytes for mini-Whirlpool and 64 for ISO Whirlpool.
+///
+/// `Display` and `{:x}` give lowercase hex and `{:X}` uppercase hex, always two digits per
+/// byte. Comparing two digests takes the same time wherever they differ.
+#[derive(Clone, Eq)]
+pub struct Digest(Vec<u8>);
+
+impl Digest {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn to_base64(&self) -> String {
+        to_base64(&self.0)
+    }
+}
+
+impl From<Vec<u8>> for Digest {
+    fn from(bytes: Vec<u8>) -> Self {
+        Digest(bytes)
+    }
+}
+
+impl From<&[u8]> for Digest {
+    fn from(bytes: &[u8]) -> Self {
+        Digest(bytes.to_vec())
+    }
+}
+
+impl AsRef<[u8]> for Digest {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl PartialEq for Digest {
+    /// Looks at every byte instead of stopping at the first difference, so the time taken
+    /// does not tell how much of a guessed digest was right. The length is not secret.
+    fn eq(&self, other: &Digest) -> bool {
+        if self.0.len() != other.0.len() {
+            return false;
+        }
+        let difference = self
+            .0
+            .iter()
+            .zip(other.0.iter())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b));
+        std::hint::black_box(difference) == 0
+    }
+}
+
+impl Hash for Digest {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl FromStr for Digest {
+    type Err = HexError;
+
+    /// Parses hex in either case, the length is up to the caller to check
+    fn from_str(hex: &str) -> Result<Digest, HexError> {
+        from_hex(hex).map(Digest)
+    }
+}
+
+impl std::fmt::LowerHex for Digest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&to_hex(&self.0))
+    }
+}
+
+impl std::fmt::UpperHex for Digest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&to_hex(&self.0).to_uppercase())
+    }
+}
+
+impl std::fmt::Display for Digest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::LowerHex::fmt(self, f)
+    }
+}
+
+impl std::fmt::Debug for Digest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Digest({:x})", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let digest = Digest::from(vec![0x0B, 0x00, 0xAF, 0x10]);
+        assert_eq!(digest.to_string(), "0b00af10");
+        assert_eq!(format!("{:x}", digest), "0b00af10");
+        assert_eq!(format!("{:X}", digest), "0B00AF10");
+        assert_eq!(format!("{:?}", digest), "Digest(0b00af10)");
+        assert_eq!(digest.to_base64(), "CwCvEA==");
+        assert_eq!(digest.as_ref(), [0x0B, 0x00, 0xAF, 0x10]);
+        assert_eq!(digest.len(), 4);
+    }
+
+    #[test]
+    fn test_parse_and_compare() {
+        let digest: Digest = "0B00af10".parse().unwrap();
+        assert_eq!(digest, Digest::from(&[0x0B, 0x00, 0xAF, 0x10][..]));
+        assert_ne!(digest, Digest::from(vec![0x0B, 0x00, 0xAF, 0x11]));
+        assert_ne!(digest, Digest::from(vec![0x0B, 0x00, 0xAF]));
+        assert_eq!(digest.to_string().parse::<Digest>(), Ok(digest));
+        assert_eq!("0b0".parse::<Digest>(), Err(HexError::OddLength(3)));
+        assert!("xyz0".parse::<Digest>().is_err());
+
+        let mut set = std::collections::HashSet::new();
+        set.insert(Digest::from(vec![1, 2]));
+        assert!(set.contains(&"0102".parse().unwrap()));
+    }
+}
@@ -0,0 +1,183 @@
+/// Why a string is not valid hex
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum HexError {
+    /// Every byte takes two digits
+    OddLength(usize),
+    InvalidDigit {
+        index: usize,
+        digit: char,
+    },
+}
+
+impl std::fmt::Display for HexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HexError::OddLength(length) => write!(f, "odd number of hex digits ({})", length),
+            HexError::InvalidDigit { index, digit } => {
+                write!(f, "invalid hex digit {:?} at position {}", digit, index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HexError {}
+
+/// Why a string is not valid base64
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Base64Error {
+    /// The characters do not add up to whole bytes, or the padding does not fill the last group
+    InvalidLength(usize),
+    InvalidCharacter {
+        index: usize,
+        character: char,
+    },
+}
+
+impl std::fmt::Display for Base64Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Base64Error::InvalidLength(length) => {
+                write!(f, "invalid base64 length ({})", length)
+            }
+            Base64Error::InvalidCharacter { index, character } => write!(
+                f,
+                "invalid base64 character {:?} at position {}",
+                character, index
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Base64Error {}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Two lowercase digits per byte
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+/// Accepts both lowercase and uppercase digits
+pub fn from_hex(hex: &str) -> Result<Vec<u8>, HexError> {
+    if let Some((index, digit)) = hex.char_indices().find(|(_, c)| !c.is_ascii_hexdigit()) {
+        return Err(HexError::InvalidDigit { index, digit });
+    }
+    if hex.len() % 2 != 0 {
+        return Err(HexError::OddLength(hex.len()));
+    }
+    Ok((0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect())
+}
+
+/// Standard base64 from RFC 4648, padded with `=`
+pub fn to_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let mut group = [0u8; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let bits = u32::from(group[0]) << 16 | u32::from(group[1]) << 8 | u32::from(group[2]);
+        // n bytes give n + 1 characters, the rest of the four is padding
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[(bits >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Inverse of [`to_base64`]. The padding may be left out, but if it is there it has to
+/// complete the last group of four characters.
+pub fn from_base64(encoded: &str) -> Result<Vec<u8>, Base64Error> {
+    let data = encoded.trim_end_matches('=');
+    let padding = encoded.len() - data.len();
+    if padding > 2 || (padding > 0 && encoded.len() % 4 != 0) || data.len() % 4 == 1 {
+        return Err(Base64Error::InvalidLength(encoded.len()));
+    }
+
+    let mut decoded = Vec::with_capacity(data.len() / 4 * 3 + 2);
+    let mut bits = 0u32;
+    for (i, (index, character)) in data.char_indices().enumerate() {
+        let value = BASE64_ALPHABET
+            .iter()
+            .position(|&x| x as char == character)
+            .ok_or(Base64Error::InvalidCharacter { index, character })?;
+        bits = bits << 6 | value as u32;
+        if i % 4 == 3 {
+            decoded.extend_from_slice(&bits.to_be_bytes()[1..]);
+            bits = 0;
+        }
+    }
+    // A partial group of n characters holds n - 1 bytes
+    match data.len() % 4 {
+        2 => decoded.push((bits >> 4) as u8),
+        3 => decoded.extend_from_slice(&((bits >> 2) as u16).to_be_bytes()),
+        _ => {}
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(to_hex(&[0x00, 0x0B, 0xAF]), "000baf");
+        assert_eq!(from_hex("000bAF"), Ok(vec![0x00, 0x0B, 0xAF]));
+        assert_eq!(from_hex(""), Ok(vec![]));
+        assert_eq!(from_hex("abc"), Err(HexError::OddLength(3)));
+        assert_eq!(
+            from_hex("0g"),
+            Err(HexError::InvalidDigit {
+                index: 1,
+                digit: 'g'
+            })
+        );
+        // Multi-byte characters must not be sliced in the middle
+        assert!(from_hex("é1").is_err());
+    }
+
+    #[test]
+    fn test_base64() {
+        // RFC 4648 test vectors
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (input, expected) in vectors.iter() {
+            assert_eq!(to_base64(input.as_bytes()), *expected);
+        }
+        assert_eq!(to_base64(&[0xFB, 0xFF]), "+/8=");
+
+        for (expected, input) in vectors.iter() {
+            assert_eq!(from_base64(input), Ok(expected.as_bytes().to_vec()));
+            assert_eq!(
+                from_base64(input.trim_end_matches('=')),
+                Ok(expected.as_bytes().to_vec())
+            );
+        }
+        assert_eq!(from_base64("+/8="), Ok(vec![0xFB, 0xFF]));
+        assert_eq!(from_base64("Zg="), Err(Base64Error::InvalidLength(3)));
+        assert_eq!(from_base64("Zm9vY"), Err(Base64Error::InvalidLength(5)));
+        assert_eq!(from_base64("Z==="), Err(Base64Error::InvalidLength(4)));
+        assert_eq!(
+            from_base64("Zm=v"),
+            Err(Base64Error::InvalidCharacter {
+                index: 2,
+                character: '='
+            })
+        );
+        assert!(from_base64("Zm9v\n").is_err());
+    }
+}
@@ -0,0 +1,133 @@
+use super::encoding::{Base64Error, HexError};
+use super::padding::PaddingError;
+use super::params::ParamsError;
+
+/// Everything that can go wrong while hashing, returned by the functions of `core` and
+/// `cipher`. The more specific errors of the other modules convert into it with `?`.
+#[derive(Debug)]
+pub enum WhirlpoolError {
+    /// Most often a message too long for the length field of the padding scheme
+    Padding(PaddingError),
+    InvalidParams(ParamsError),
+    InvalidHex(HexError),
+    InvalidBase64(Base64Error),
+    /// A block, key or matrix was given the wrong number of bytes
+    InvalidLength {
+        length: usize,
+        expected: usize,
+    },
+    Io(std::io::Error),
+}
+
+impl WhirlpoolError {
+    /// The exit code both binaries use for this error. 1 is left for a failed check or
+    /// search and 2 for a wrong command line.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            WhirlpoolError::InvalidParams(_) => 3,
+            WhirlpoolError::Padding(_) => 4,
+            WhirlpoolError::InvalidHex(_)
+            | WhirlpoolError::InvalidBase64(_)
+            | WhirlpoolError::InvalidLength { .. } => 5,
+            WhirlpoolError::Io(_) => 6,
+        }
+    }
+}
+
+impl std::fmt::Display for WhirlpoolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WhirlpoolError::Padding(e) => write!(f, "{}", e),
+            WhirlpoolError::InvalidParams(e) => write!(f, "invalid parameters: {}", e),
+            WhirlpoolError::InvalidHex(e) => write!(f, "malformed hex: {}", e),
+            WhirlpoolError::InvalidBase64(e) => write!(f, "malformed base64: {}", e),
+            WhirlpoolError::InvalidLength { length, expected } => {
+                write!(f, "expected {} bytes, got {}", expected, length)
+            }
+            WhirlpoolError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for WhirlpoolError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WhirlpoolError::Padding(e) => Some(e),
+            WhirlpoolError::InvalidParams(e) => Some(e),
+            WhirlpoolError::InvalidHex(e) => Some(e),
+            WhirlpoolError::InvalidBase64(e) => Some(e),
+            WhirlpoolError::Io(e) => Some(e),
+            WhirlpoolError::InvalidLength { .. } => None,
+        }
+    }
+}
+
+impl From<PaddingError> for WhirlpoolError {
+    fn from(e: PaddingError) -> Self {
+        WhirlpoolError::Padding(e)
+    }
+}
+
+impl From<ParamsError> for WhirlpoolError {
+    fn from(e: ParamsError) -> Self {
+        WhirlpoolError::InvalidParams(e)
+    }
+}
+
+impl From<HexError> for WhirlpoolError {
+    fn from(e: HexError) -> Self {
+        WhirlpoolError::InvalidHex(e)
+    }
+}
+
+impl From<Base64Error> for WhirlpoolError {
+    fn from(e: Base64Error) -> Self {
+        WhirlpoolError::InvalidBase64(e)
+    }
+}
+
+impl From<std::io::Error> for WhirlpoolError {
+    fn from(e: std::io::Error) -> Self {
+        WhirlpoolError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hash;
+    use crate::params::{Reduction, WhirlpoolParams};
+
+    #[test]
+    fn test_conversions() {
+        let too_long = hash(&WhirlpoolParams::mini(), vec![0; 65536]).unwrap_err();
+        assert!(matches!(
+            too_long,
+            WhirlpoolError::Padding(PaddingError::MessageTooLong {
+                length: 65536,
+                max_length: 65535
+            })
+        ));
+        assert_eq!(
+            too_long.to_string(),
+            "message of 65536 bytes is longer than the padding allows (65535 bytes)"
+        );
+        assert_eq!(too_long.exit_code(), 4);
+
+        let params = WhirlpoolParams::mini().reduced(Reduction {
+            rounds: Some(0),
+            ..Reduction::default()
+        });
+        let invalid = WhirlpoolError::from(params.unwrap_err());
+        assert_eq!(
+            invalid.to_string(),
+            "invalid parameters: at least one round is required"
+        );
+        assert!(std::error::Error::source(&invalid).is_some());
+
+        let hex = WhirlpoolError::from("0g".parse::<crate::digest::Digest>().unwrap_err());
+        assert_eq!(hex.exit_code(), 5);
+        let io = WhirlpoolError::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert_eq!(io.exit_code(), 6);
+    }
+}
@@ -1,5 +1,15 @@
 pub mod matrix;
 pub mod bipoly;
+pub mod poly;
+pub mod cipher;
 pub mod core;
 pub mod constants;
+pub mod padding;
+pub mod params;
 pub mod permutations;
+pub mod diffusion;
+pub mod sbox;
+pub mod trace;
+pub mod digest;
+pub mod encoding;
+pub mod error;
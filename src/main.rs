@@ -1,21 +1,968 @@
-use std::io;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use whirlpool::constants::LENGTH_SIZE;
+use whirlpool::core::Hasher;
+use whirlpool::digest::Digest;
+use whirlpool::encoding::{from_base64, from_hex, to_hex};
+use whirlpool::error::WhirlpoolError;
+use whirlpool::padding::Padding;
+use whirlpool::params::{Reduction, WhirlpoolParams};
 
-fn main() -> io::Result<()> {
-    let args: Vec<String> = std::env::args().collect();
-    let input = if args.len() > 1 {
-        args.get(1).unwrap().clone()
-    } else {
-        let mut buffer = String::new();
-        io::stdin().read_line(&mut buffer)?;
-        buffer
+const USAGE: &str = "Usage: hash [OPTIONS] [FILE]...
+
+Prints the mini-Whirlpool digest of every FILE as 'digest  filename'.
+With no FILE, or when FILE is -, standard input is hashed.
+With --check every FILE is read as a list of such lines and the files in it are verified.
+Messages given on the command line, with --string, --hex, --base64 or --empty, are
+hashed in the order given and only their digests are printed.
+
+Options:
+    -c, --check           Verify the digests listed in the FILEs
+    --quiet               With --check, do not print OK for files that match
+    --status              With --check, print nothing and only set the exit code
+    --string              Hash the arguments themselves instead of the files they name
+    --hex <BYTES>         Hash the bytes written in hex, spaces between them are ignored
+    --base64 <DATA>       Hash the bytes written in base64
+    --empty               Hash the empty message
+    --repeat <N>          Hash every message of the command line repeated N times
+    --format <FORMAT>     Print the digests as hex (the default), HEX, base64, raw bytes
+                          or c-array, a list of 0xXX bytes for a Rust or C array
+    --trace               Print the key and the data state after every step of every round
+    --trace-json          Print the same trace as JSON, one object per input
+    --padding <SCHEME>    Pad with length, the 2 byte length field of mini-Whirlpool that
+                          allows at most 65535 bytes (the default), or strengthened, a 1 bit
+                          and a 64-bit length for messages of any size
+    --rounds <N>          Hash with only the first N of the 6 rounds
+    --no-final-mix        Leave out MR in the last round
+    --no-feed-forward     Leave out the feed-forward of the compression function
+    --no-key-schedule     Use the chaining value as every round key
+    --                    Treat the remaining arguments as files
+    -h, --help            Print this help
+
+Exit status:
+    0  every input was hashed, or every listed file matched with --check
+    1  a listed file did not match or could not be read with --check
+    2  the command line is wrong
+    3  the reduced-round options give invalid parameters
+    4  a message is longer than the padding allows
+    5  a message given with --hex or --base64 is malformed
+    6  an input could not be read";
+
+/// Bytes read from a file at a time
+const BUFFER_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, PartialEq)]
+enum Input {
+    Stdin,
+    File(PathBuf),
+    String(String),
+    /// Bytes decoded from `--hex`, `--base64` or `--empty`
+    Bytes(Vec<u8>),
+}
+
+impl std::fmt::Display for Input {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Input::Stdin => write!(f, "-"),
+            Input::File(path) => write!(f, "{}", path.display()),
+            Input::String(string) => write!(f, "{:?}", string),
+            Input::Bytes(bytes) => write!(f, "hex {}", to_hex(bytes)),
+        }
+    }
+}
+
+/// What to do with every input
+#[derive(Debug, PartialEq)]
+enum Output {
+    Digest,
+    /// Every intermediate state as text, `--trace`
+    Trace,
+    /// Every intermediate state as JSON, `--trace-json`
+    TraceJson,
+    /// Read the input as a checksum file and verify the files it lists, `--check`
+    Check(Verbosity),
+}
+
+/// How digests are printed, `--format`
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Format {
+    Hex,
+    UpperHex,
+    Base64,
+    /// The digest bytes themselves, without a name or a newline
+    Raw,
+    /// `0x4A, 0x07, ...` eight bytes to a line, preceded by the name as a comment
+    CArray,
+}
+
+/// How much `--check` prints
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Verbosity {
+    /// A line for every file
+    All,
+    /// Only the files that failed, `--quiet`
+    Failures,
+    /// Nothing, `--status`
+    Silent,
+}
+
+/// What a `--check` run found
+#[derive(Debug, Default, PartialEq)]
+struct CheckSummary {
+    ok: usize,
+    failed: usize,
+    missing: usize,
+    malformed: usize,
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    inputs: Vec<Input>,
+    output: Output,
+    format: Format,
+    /// How many times the messages of the command line are repeated, `--repeat`
+    repeat: usize,
+    reduction: Reduction,
+    padding: Padding,
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Hash(Options),
+    Help,
+}
+
+/// Why the command line was rejected
+#[derive(Debug)]
+enum ArgsError {
+    /// Unknown or conflicting options, or a missing value
+    Usage(String),
+    /// A message given with `--hex` or `--base64` that does not decode
+    Message {
+        option: String,
+        error: WhirlpoolError,
+    },
+}
+
+impl ArgsError {
+    fn exit_code(&self) -> u8 {
+        match self {
+            ArgsError::Usage(_) => 2,
+            ArgsError::Message { error, .. } => error.exit_code(),
+        }
+    }
+}
+
+impl std::fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArgsError::Usage(message) => write!(f, "{}", message),
+            ArgsError::Message { option, error } => {
+                write!(f, "invalid value for '{}': {}", option, error)
+            }
+        }
+    }
+}
+
+impl From<String> for ArgsError {
+    fn from(message: String) -> Self {
+        ArgsError::Usage(message)
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Command::Hash(options)) => options,
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("hash: {}", e);
+            if let ArgsError::Usage(_) = e {
+                eprintln!("Try 'hash --help' for more information.");
+            }
+            return ExitCode::from(e.exit_code());
+        }
     };
 
-    let hash = whirlpool::core::hash(input.as_bytes().into());
-    print_result(hash);
+    let params = WhirlpoolParams::mini()
+        .reduced(options.reduction)
+        .and_then(|params| params.with_padding(options.padding));
+    let params = match params {
+        Ok(params) => params,
+        Err(e) => {
+            let e = WhirlpoolError::from(e);
+            eprintln!("hash: {}", e);
+            return ExitCode::from(e.exit_code());
+        }
+    };
+
+    // Like coreutils, an unreadable input is reported and the others are still hashed.
+    // The first error decides the exit code, a failed check only gives 1.
+    let mut status = 0;
+    for input in options.inputs.iter() {
+        let result = match options.output {
+            Output::Check(verbosity) => check_input(&params, input, verbosity),
+            _ => print_input(&params, input, &options).map(|_| true),
+        };
+        match result {
+            Ok(true) => {}
+            Ok(false) if status == 0 => status = 1,
+            Ok(false) => {}
+            Err(e) => {
+                eprintln!("hash: {}: {}", input, e);
+                if status <= 1 {
+                    status = e.exit_code();
+                }
+            }
+        }
+    }
+    ExitCode::from(status)
+}
+
+fn print_input(
+    params: &WhirlpoolParams,
+    input: &Input,
+    options: &Options,
+) -> Result<(), WhirlpoolError> {
+    match options.output {
+        Output::Digest => {
+            let digest = digest_input(params, input, options.repeat)?;
+            let name = match input {
+                Input::Stdin | Input::File(_) => Some(input),
+                Input::String(_) | Input::Bytes(_) => None,
+            };
+            io::stdout()
+                .lock()
+                .write_all(&format_digest(&digest, options.format, name))?;
+        }
+        Output::Trace | Output::TraceJson => {
+            // A trace is only useful for short inputs, so reading them whole is fine
+            let mut bytes = Vec::new();
+            match input {
+                Input::Stdin => io::stdin().lock().read_to_end(&mut bytes)?,
+                Input::File(path) => File::open(path)?.read_to_end(&mut bytes)?,
+                Input::String(string) => string.as_bytes().read_to_end(&mut bytes)?,
+                Input::Bytes(message) => message.as_slice().read_to_end(&mut bytes)?,
+            };
+            let trace = whirlpool::core::hash_traced(params, bytes.repeat(options.repeat))?;
+            if options.output == Output::Trace {
+                print!("{}", trace);
+            } else {
+                println!("{}", trace.to_json());
+            }
+        }
+        Output::Check(_) => unreachable!("checksum files go through check_input"),
+    }
     Ok(())
 }
 
-fn print_result(result: [u8; 16]) {
-    result.iter().for_each(|x| print!("{:x}", x));
-    println!();
+/// Verifies the checksum file `input`, returns whether every file in it matched
+fn check_input(
+    params: &WhirlpoolParams,
+    input: &Input,
+    verbosity: Verbosity,
+) -> Result<bool, WhirlpoolError> {
+    let mut contents = String::new();
+    match input {
+        Input::Stdin => io::stdin().lock().read_to_string(&mut contents)?,
+        Input::File(path) => File::open(path)?.read_to_string(&mut contents)?,
+        Input::String(string) => string.as_bytes().read_to_string(&mut contents)?,
+        Input::Bytes(bytes) => bytes.as_slice().read_to_string(&mut contents)?,
+    };
+    let summary = check(
+        params,
+        &input.to_string(),
+        &contents,
+        verbosity,
+        &mut io::stdout().lock(),
+    )?;
+
+    if verbosity != Verbosity::Silent {
+        let warn = |count: usize, singular: &str, plural: &str| {
+            if count > 0 {
+                let what = if count == 1 { singular } else { plural };
+                eprintln!("hash: WARNING: {} {}", count, what);
+            }
+        };
+        warn(
+            summary.malformed,
+            "line is improperly formatted",
+            "lines are improperly formatted",
+        );
+        warn(
+            summary.missing,
+            "listed file could not be read",
+            "listed files could not be read",
+        );
+        warn(
+            summary.failed,
+            "computed checksum did NOT match",
+            "computed checksums did NOT match",
+        );
+    }
+    if summary.ok + summary.failed + summary.missing == 0 {
+        eprintln!(
+            "hash: {}: no properly formatted checksum lines found",
+            input
+        );
+        return Ok(false);
+    }
+    Ok(summary.failed + summary.missing + summary.malformed == 0)
+}
+
+/// Goes through the `digest  filename` lines of a checksum file, recomputes every digest
+/// and writes `filename: OK`, `FAILED` or `MISSING` to `out`, as far as `verbosity` allows.
+/// MISSING is for files that cannot be read, one that cannot be hashed has FAILED.
+/// Blank lines are skipped, any other line that does not parse is counted as malformed.
+fn check<W: Write>(
+    params: &WhirlpoolParams,
+    name: &str,
+    contents: &str,
+    verbosity: Verbosity,
+    out: &mut W,
+) -> io::Result<CheckSummary> {
+    let mut summary = CheckSummary::default();
+    for (line_number, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (expected, filename) = match parse_checksum_line(line, params.block_size()) {
+            Some(parsed) => parsed,
+            None => {
+                summary.malformed += 1;
+                if verbosity != Verbosity::Silent {
+                    eprintln!(
+                        "hash: {}: {}: improperly formatted checksum line",
+                        name,
+                        line_number + 1
+                    );
+                }
+                continue;
+            }
+        };
+        let input = match filename {
+            "-" => Input::Stdin,
+            _ => Input::File(PathBuf::from(filename)),
+        };
+        let status = match digest_input(params, &input, 1) {
+            Ok(digest) if digest == expected => {
+                summary.ok += 1;
+                "OK"
+            }
+            Ok(_) => {
+                summary.failed += 1;
+                "FAILED"
+            }
+            Err(e) => {
+                if verbosity != Verbosity::Silent {
+                    eprintln!("hash: {}: {}", filename, e);
+                }
+                if let WhirlpoolError::Io(_) = e {
+                    summary.missing += 1;
+                    "MISSING"
+                } else {
+                    summary.failed += 1;
+                    "FAILED"
+                }
+            }
+        };
+        let print = match verbosity {
+            Verbosity::All => true,
+            Verbosity::Failures => status != "OK",
+            Verbosity::Silent => false,
+        };
+        if print {
+            writeln!(out, "{}: {}", filename, status)?;
+        }
+    }
+    Ok(summary)
+}
+
+/// Splits `digest  filename` into the digest bytes and the filename, which may contain
+/// spaces. A `*` in place of the second space, the binary mode marker of coreutils, is
+/// accepted as well.
+fn parse_checksum_line(line: &str, digest_size: usize) -> Option<(Digest, &str)> {
+    let hex = line.get(..2 * digest_size)?;
+    let filename = line
+        .get(2 * digest_size..)?
+        .strip_prefix(" ")
+        .and_then(|x| x.strip_prefix(' ').or_else(|| x.strip_prefix('*')))?;
+    if filename.is_empty() {
+        return None;
+    }
+    Some((hex.parse().ok()?, filename))
+}
+
+/// The messages of the command line are hashed `repeat` times over, files and stdin once
+fn digest_input(
+    params: &WhirlpoolParams,
+    input: &Input,
+    repeat: usize,
+) -> Result<Digest, WhirlpoolError> {
+    match input {
+        Input::Stdin => digest_reader(params, io::stdin().lock()),
+        Input::File(path) => digest_reader(params, File::open(path)?),
+        Input::String(string) => digest_message(params, string.as_bytes(), repeat),
+        Input::Bytes(bytes) => digest_message(params, bytes, repeat),
+    }
+}
+
+fn digest_message(
+    params: &WhirlpoolParams,
+    message: &[u8],
+    repeat: usize,
+) -> Result<Digest, WhirlpoolError> {
+    let mut hasher = Hasher::new(params);
+    for _ in 0..repeat {
+        hasher.update(message)?;
+    }
+    hasher.finalize()
+}
+
+/// The digest as printed for an input, `name` is left out for messages of the command line
+fn format_digest(digest: &Digest, format: Format, name: Option<&Input>) -> Vec<u8> {
+    let text = match format {
+        Format::Hex => format!("{:x}", digest),
+        Format::UpperHex => format!("{:X}", digest),
+        Format::Base64 => digest.to_base64(),
+        Format::Raw => return digest.as_bytes().to_vec(),
+        Format::CArray => {
+            let rows: Vec<String> = digest
+                .as_bytes()
+                .chunks(8)
+                .map(|row| {
+                    let bytes: Vec<String> = row.iter().map(|x| format!("0x{:02X},", x)).collect();
+                    bytes.join(" ")
+                })
+                .collect();
+            let comment = name.map(|x| format!("// {}\n", x)).unwrap_or_default();
+            return format!("{}{}\n", comment, rows.join("\n")).into_bytes();
+        }
+    };
+    match name {
+        Some(name) => format!("{}  {}\n", text, name),
+        None => format!("{}\n", text),
+    }
+    .into_bytes()
+}
+
+/// Streams everything `reader` yields through the hasher
+fn digest_reader<R: Read>(
+    params: &WhirlpoolParams,
+    mut reader: R,
+) -> Result<Digest, WhirlpoolError> {
+    let mut hasher = Hasher::new(params);
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => hasher.update(&buffer[..n])?,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    hasher.finalize()
+}
+
+/// An input named on the command line, which kind of input an operand is depends on
+/// `--string` and is only known once every argument has been seen
+enum Argument {
+    Operand(String),
+    Message(Vec<u8>),
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Command, ArgsError> {
+    let mut output = Output::Digest;
+    let mut format = None;
+    let mut repeat = None;
+    let mut reduction = Reduction::default();
+    let mut padding = Padding::Length { size: LENGTH_SIZE };
+    let mut strings = false;
+    let mut check = false;
+    let mut verbosity = Verbosity::All;
+    let mut arguments = Vec::new();
+    // The last option that only makes sense for the digests of messages or files
+    let mut digest_option = None;
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            arguments.extend(args.by_ref().map(Argument::Operand));
+            break;
+        }
+        if !arg.starts_with('-') || arg == "-" {
+            arguments.push(Argument::Operand(arg));
+            continue;
+        }
+
+        // Both `--option value` and `--option=value` are accepted
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+        let mut value = || match inline_value {
+            Some(value) => Ok(value.to_string()),
+            None => args
+                .next()
+                .ok_or_else(|| format!("option '{}' requires a value", name)),
+        };
+
+        if [
+            "--string",
+            "--hex",
+            "--base64",
+            "--empty",
+            "--repeat",
+            "--format",
+            "--trace",
+            "--trace-json",
+        ]
+        .contains(&name.as_str())
+        {
+            digest_option = Some(name.clone());
+        }
+
+        match name.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--string" => strings = true,
+            "--hex" => {
+                let hex: String = value()?.split_whitespace().collect();
+                let bytes = from_hex(&hex).map_err(|e| ArgsError::Message {
+                    option: name.clone(),
+                    error: e.into(),
+                })?;
+                arguments.push(Argument::Message(bytes));
+            }
+            "--base64" => {
+                let bytes = from_base64(value()?.trim()).map_err(|e| ArgsError::Message {
+                    option: name.clone(),
+                    error: e.into(),
+                })?;
+                arguments.push(Argument::Message(bytes));
+            }
+            "--empty" => arguments.push(Argument::Message(Vec::new())),
+            "--repeat" => {
+                let count = value()?;
+                let count = count
+                    .parse()
+                    .map_err(|_| format!("invalid value '{}' for '{}'", count, name))?;
+                repeat = Some(count);
+            }
+            "--format" => {
+                format = Some(match value()?.as_str() {
+                    "hex" => Format::Hex,
+                    "HEX" => Format::UpperHex,
+                    "base64" => Format::Base64,
+                    "raw" => Format::Raw,
+                    "c-array" => Format::CArray,
+                    other => {
+                        return Err(format!(
+                            "unknown format '{}', expected hex, HEX, base64, raw or c-array",
+                            other
+                        )
+                        .into())
+                    }
+                })
+            }
+            "-c" | "--check" => check = true,
+            "--quiet" => verbosity = Verbosity::Failures,
+            "--status" => verbosity = Verbosity::Silent,
+            "--trace" => output = Output::Trace,
+            "--trace-json" => output = Output::TraceJson,
+            "--rounds" => {
+                let rounds = value()?;
+                let rounds = rounds
+                    .parse()
+                    .map_err(|_| format!("invalid value '{}' for '{}'", rounds, name))?;
+                reduction.rounds = Some(rounds);
+            }
+            "--padding" => {
+                padding = match value()?.as_str() {
+                    "length" => Padding::Length { size: LENGTH_SIZE },
+                    "strengthened" => Padding::Strengthened { size: 8 },
+                    other => {
+                        return Err(format!(
+                            "unknown padding '{}', expected length or strengthened",
+                            other
+                        )
+                        .into())
+                    }
+                }
+            }
+            "--no-final-mix" => reduction.final_mix = false,
+            "--no-feed-forward" => reduction.feed_forward = false,
+            "--no-key-schedule" => reduction.key_schedule = false,
+            _ => return Err(format!("unexpected argument '{}'", arg).into()),
+        }
+    }
+
+    if check {
+        if let Some(name) = digest_option {
+            return Err(format!(
+                "--check verifies checksum files and cannot be combined with '{}'",
+                name
+            )
+            .into());
+        }
+        output = Output::Check(verbosity);
+    } else if verbosity != Verbosity::All {
+        return Err("--quiet and --status only make sense with --check"
+            .to_string()
+            .into());
+    }
+    if format.is_some() && output != Output::Digest {
+        return Err("--format only applies to digests, not to --trace"
+            .to_string()
+            .into());
+    }
+
+    if strings && !arguments.iter().any(|x| matches!(x, Argument::Operand(_))) {
+        return Err("--string requires at least one argument".to_string().into());
+    }
+    let inputs: Vec<Input> = if arguments.is_empty() {
+        vec![Input::Stdin]
+    } else {
+        arguments
+            .into_iter()
+            .map(|x| match x {
+                Argument::Message(bytes) => Input::Bytes(bytes),
+                Argument::Operand(string) if strings => Input::String(string),
+                Argument::Operand(path) if path == "-" => Input::Stdin,
+                Argument::Operand(path) => Input::File(PathBuf::from(path)),
+            })
+            .collect()
+    };
+    if repeat.is_some()
+        && inputs
+            .iter()
+            .any(|x| matches!(x, Input::Stdin | Input::File(_)))
+    {
+        return Err(
+            "--repeat only applies to messages given with --string, --hex, --base64 or --empty"
+                .to_string()
+                .into(),
+        );
+    }
+
+    Ok(Command::Hash(Options {
+        inputs,
+        output,
+        format: format.unwrap_or(Format::Hex),
+        repeat: repeat.unwrap_or(1),
+        reduction,
+        padding,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, ArgsError> {
+        parse_args(args.iter().map(|x| x.to_string()))
+    }
+
+    fn options(args: &[&str]) -> Options {
+        match parse(args) {
+            Ok(Command::Hash(options)) => options,
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_inputs() {
+        assert_eq!(options(&[]).inputs, [Input::Stdin]);
+        assert_eq!(
+            options(&["a.txt", "-", "b c"]).inputs,
+            [
+                Input::File(PathBuf::from("a.txt")),
+                Input::Stdin,
+                Input::File(PathBuf::from("b c"))
+            ]
+        );
+        assert_eq!(
+            options(&["--", "--string", "-"]).inputs,
+            [Input::File(PathBuf::from("--string")), Input::Stdin]
+        );
+        assert_eq!(
+            options(&["--string", "Hello World", "-"]).inputs,
+            [
+                Input::String("Hello World".to_string()),
+                Input::String("-".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_options() {
+        let options = options(&["--trace-json", "--rounds=3", "--no-final-mix", "x"]);
+        assert_eq!(options.output, Output::TraceJson);
+        assert_eq!(
+            options.reduction,
+            Reduction {
+                rounds: Some(3),
+                final_mix: false,
+                ..Reduction::default()
+            }
+        );
+        assert!(matches!(parse(&["-h"]), Ok(Command::Help)));
+        assert!(parse(&["--string"]).is_err());
+        assert!(parse(&["--rounds"]).is_err());
+        assert!(parse(&["--rounds", "many"]).is_err());
+        assert!(parse(&["--frobnicate"]).is_err());
+    }
+
+    #[test]
+    fn test_padding() {
+        assert_eq!(
+            options(&["x"]).padding,
+            Padding::Length { size: LENGTH_SIZE }
+        );
+        let options = options(&["--padding", "strengthened", "x"]);
+        assert_eq!(options.padding, Padding::Strengthened { size: 8 });
+        assert!(parse(&["--padding", "zero", "x"]).is_err());
+
+        // Only the strengthened padding takes messages over 64 KiB
+        let message = vec![0xAA; 70000];
+        let mini = WhirlpoolParams::mini();
+        assert_eq!(
+            digest_message(&mini, &message, 1).unwrap_err().exit_code(),
+            4
+        );
+        assert_eq!(
+            digest_reader(&mini, message.as_slice())
+                .unwrap_err()
+                .exit_code(),
+            4
+        );
+        let strengthened = mini.with_padding(options.padding).unwrap();
+        assert_eq!(
+            digest_reader(&strengthened, message.as_slice()).unwrap(),
+            digest_message(&strengthened, &message, 1).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_messages() {
+        assert_eq!(
+            options(&[
+                "--hex",
+                "00 0102",
+                "--base64=YQ==",
+                "--empty",
+                "-",
+                "--string",
+                "b"
+            ])
+            .inputs,
+            [
+                Input::Bytes(vec![0, 1, 2]),
+                Input::Bytes(b"a".to_vec()),
+                Input::Bytes(Vec::new()),
+                Input::String("-".to_string()),
+                Input::String("b".to_string())
+            ]
+        );
+        let options = self::options(&["--string", "a", "--repeat", "3", "--format", "c-array"]);
+        assert_eq!((options.repeat, options.format), (3, Format::CArray));
+        assert_eq!(self::options(&["--empty"]).repeat, 1);
+
+        assert_eq!(parse(&["--hex", "0g"]).unwrap_err().exit_code(), 5);
+        assert_eq!(parse(&["--base64", "Y"]).unwrap_err().exit_code(), 5);
+        assert_eq!(parse(&["--hex"]).unwrap_err().exit_code(), 2);
+        assert!(parse(&["--repeat", "2", "a.txt"]).is_err());
+        assert!(parse(&["--repeat", "2"]).is_err());
+        assert!(parse(&["--format", "octal"]).is_err());
+        assert!(parse(&["--format", "raw", "--trace"]).is_err());
+        assert!(parse(&["--check", "--hex", "00"]).is_err());
+        assert!(parse(&["--string", "--empty"]).is_err());
+    }
+
+    #[test]
+    fn test_repeat() {
+        // The "a".repeat(48000) vector of the core tests
+        let params = WhirlpoolParams::mini();
+        assert_eq!(
+            digest_message(&params, b"a", 48000).unwrap().to_string(),
+            "4a071909c7a6bd415bb8a24187b361eb"
+        );
+        assert_eq!(
+            digest_message(&params, b"", 10).unwrap(),
+            whirlpool::core::hash(&params, Vec::new()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        let digest: Digest = "8e656fbcb4a3dfc4a15f9690d2cc1263".parse().unwrap();
+        let file = Input::File(PathBuf::from("a.txt"));
+        let print = |format, name| String::from_utf8(format_digest(&digest, format, name)).unwrap();
+        assert_eq!(
+            print(Format::Hex, Some(&file)),
+            "8e656fbcb4a3dfc4a15f9690d2cc1263  a.txt\n"
+        );
+        assert_eq!(
+            print(Format::UpperHex, None),
+            "8E656FBCB4A3DFC4A15F9690D2CC1263\n"
+        );
+        assert_eq!(print(Format::Base64, None), "jmVvvLSj38ShX5aQ0swSYw==\n");
+        assert_eq!(
+            print(Format::CArray, Some(&file)),
+            "// a.txt\n\
+             0x8E, 0x65, 0x6F, 0xBC, 0xB4, 0xA3, 0xDF, 0xC4,\n\
+             0xA1, 0x5F, 0x96, 0x90, 0xD2, 0xCC, 0x12, 0x63,\n"
+        );
+        assert_eq!(
+            format_digest(&digest, Format::Raw, Some(&file)),
+            digest.as_ref()
+        );
+    }
+
+    #[test]
+    fn test_digest_reader() {
+        // Reading in pieces gives the same digest as hashing the whole message
+        let params = WhirlpoolParams::mini();
+        let message: Vec<u8> = (0..60_000).map(|x| x as u8).collect();
+        let reader = message[..1000]
+            .chain(&message[1000..1017])
+            .chain(&message[1017..]);
+        assert_eq!(
+            digest_reader(&params, reader).unwrap(),
+            whirlpool::core::hash(&params, message).unwrap()
+        );
+        assert_eq!(
+            digest_reader(&params, &b"1234567890"[..])
+                .unwrap()
+                .to_string(),
+            "8e656fbcb4a3dfc4a15f9690d2cc1263"
+        );
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("hash-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_parse_checksum_line() {
+        let hex = "8e656fbcb4a3dfc4a15f9690d2cc1263";
+        let digest: Digest = hex.parse().unwrap();
+        fn parse(line: &str) -> Option<(Digest, &str)> {
+            parse_checksum_line(line, 16)
+        }
+        assert_eq!(
+            parse(&format!("{}  a.txt", hex)),
+            Some((digest.clone(), "a.txt"))
+        );
+        assert_eq!(
+            parse(&format!("{}  my file  .txt", hex)),
+            Some((digest.clone(), "my file  .txt"))
+        );
+        assert_eq!(
+            parse(&format!("{} *a.txt", hex.to_uppercase())),
+            Some((digest, "a.txt"))
+        );
+
+        assert_eq!(parse(&format!("{} a.txt", hex)), None);
+        assert_eq!(parse(&format!("{}  ", hex)), None);
+        assert_eq!(parse(&format!("{}  a.txt", &hex[2..])), None);
+        assert_eq!(parse(&format!("{}00  a.txt", hex)), None);
+        assert_eq!(parse(&format!("zz{}  a.txt", &hex[2..])), None);
+        assert_eq!(parse("a.txt"), None);
+        assert_eq!(parse(""), None);
+    }
+
+    #[test]
+    fn test_check() {
+        let params = WhirlpoolParams::mini();
+        let dir = temp_dir("check");
+        let good = dir.join("with spaces .txt");
+        let bad = dir.join("bad.txt");
+        std::fs::write(&good, "1234567890").unwrap();
+        std::fs::write(&bad, "changed").unwrap();
+        let missing = dir.join("missing.txt");
+
+        let hex = "8e656fbcb4a3dfc4a15f9690d2cc1263";
+        let contents = format!(
+            "{hex}  {}\n\n{hex} *{}\n{hex}  {}\nnot a checksum line\n",
+            good.display(),
+            bad.display(),
+            missing.display(),
+            hex = hex
+        );
+        let run = |verbosity| {
+            let mut out = Vec::new();
+            let summary = check(&params, "SUMS", &contents, verbosity, &mut out).unwrap();
+            (summary, String::from_utf8(out).unwrap())
+        };
+
+        let (summary, out) = run(Verbosity::All);
+        assert_eq!(
+            summary,
+            CheckSummary {
+                ok: 1,
+                failed: 1,
+                missing: 1,
+                malformed: 1
+            }
+        );
+        assert_eq!(
+            out,
+            format!(
+                "{}: OK\n{}: FAILED\n{}: MISSING\n",
+                good.display(),
+                bad.display(),
+                missing.display()
+            )
+        );
+
+        let (quiet, out) = run(Verbosity::Failures);
+        assert_eq!(quiet, summary);
+        assert!(!out.contains("OK"));
+        assert_eq!(out.lines().count(), 2);
+
+        let (status, out) = run(Verbosity::Silent);
+        assert_eq!(status, summary);
+        assert_eq!(out, "");
+
+        // A file that is there but too long for the padding is not missing
+        let long = dir.join("long.txt");
+        std::fs::write(&long, vec![0; 65536]).unwrap();
+        let contents = format!("{}  {}\n", hex, long.display());
+        let mut out = Vec::new();
+        let summary = check(&params, "SUMS", &contents, Verbosity::All, &mut out).unwrap();
+        assert_eq!(
+            summary,
+            CheckSummary {
+                failed: 1,
+                ..CheckSummary::default()
+            }
+        );
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!("{}: FAILED\n", long.display())
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_check_options() {
+        assert_eq!(
+            options(&["--check", "--quiet", "SUMS"]).output,
+            Output::Check(Verbosity::Failures)
+        );
+        assert_eq!(
+            options(&["-c", "--status"]).output,
+            Output::Check(Verbosity::Silent)
+        );
+        assert_eq!(options(&["-c"]).inputs, [Input::Stdin]);
+        assert!(parse(&["--quiet", "x"]).is_err());
+        assert!(parse(&["--check", "--string", "x"]).is_err());
+        assert!(parse(&["--check", "--trace", "x"]).is_err());
+    }
 }
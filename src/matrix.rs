@@ -1,15 +1,133 @@
 use super::bipoly::BiPoly;
+use super::constants::MODULO;
+use super::error::WhirlpoolError;
 
+/// Square N x N state matrix over GF(2^8) reduced modulo `M`
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
-pub struct Matrix(pub [[BiPoly; 4]; 4]);
+pub struct Matrix<const N: usize = 4, const M: u16 = MODULO>(pub [[BiPoly<M>; N]; N]);
 
-impl Matrix {
-    pub fn zeros() -> Matrix {
-        Matrix([[BiPoly(0); 4]; 4])
+/// Column vector of N elements of GF(2^8) reduced modulo `M`
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Vector<const N: usize = 4, const M: u16 = MODULO>(pub [BiPoly<M>; N]);
+
+impl<const N: usize, const M: u16> Matrix<N, M> {
+    pub fn zeros() -> Matrix<N, M> {
+        Matrix([[BiPoly(0); N]; N])
+    }
+
+    pub fn identity() -> Matrix<N, M> {
+        let mut identity = Matrix::zeros();
+        for i in 0..N {
+            identity.0[i][i] = BiPoly(1);
+        }
+        identity
+    }
+
+    /// Every row is the one above rotated right by one position, starting from `first_row`
+    pub fn circulant(first_row: [BiPoly<M>; N]) -> Matrix<N, M> {
+        let mut circulant = Matrix::zeros();
+        for i in 0..N {
+            for j in 0..N {
+                circulant.0[i][j] = first_row[(j + N - i) % N];
+            }
+        }
+        circulant
+    }
+
+    pub fn transpose(&self) -> Matrix<N, M> {
+        let mut transposed = Matrix::zeros();
+        for i in 0..N {
+            for j in 0..N {
+                transposed.0[j][i] = self.0[i][j];
+            }
+        }
+        transposed
+    }
+
+    /// Determinant by Gaussian elimination: the product of the pivots of the upper triangular
+    /// form. Swapping rows would flip the sign, which does not matter in characteristic 2.
+    pub fn determinant(&self) -> BiPoly<M> {
+        let mut a = *self;
+        let mut determinant = BiPoly(1);
+        for col in 0..N {
+            let pivot = match (col..N).find(|&row| a.0[row][col] != BiPoly(0)) {
+                Some(pivot) => pivot,
+                None => return BiPoly(0),
+            };
+            a.0.swap(col, pivot);
+            determinant *= a.0[col][col];
+
+            for row in col + 1..N {
+                let factor = a.0[row][col] / a.0[col][col];
+                for j in col..N {
+                    a.0[row][j] -= factor * a.0[col][j];
+                }
+            }
+        }
+        determinant
+    }
+
+    /// Fills the matrix row by row, `bytes` has to be exactly N * N bytes long
+    pub fn from_bytes(bytes: &[u8]) -> Result<Matrix<N, M>, WhirlpoolError> {
+        if bytes.len() != N * N {
+            return Err(WhirlpoolError::InvalidLength {
+                length: bytes.len(),
+                expected: N * N,
+            });
+        }
+        Ok(Matrix::from_block(bytes))
+    }
+
+    /// [`Matrix::from_bytes`] for blocks whose length the caller already made sure of
+    pub(crate) fn from_block(bytes: &[u8]) -> Matrix<N, M> {
+        debug_assert_eq!(bytes.len(), N * N);
+        let mut matrix = Matrix::zeros();
+        for (row, chunk) in matrix.0.iter_mut().zip(bytes.chunks(N)) {
+            for (cell, &byte) in row.iter_mut().zip(chunk) {
+                *cell = BiPoly(byte);
+            }
+        }
+        matrix
+    }
+
+    /// Reads the matrix row by row
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.iter().flatten().map(|x| x.0).collect()
+    }
+
+    /// Inverts the matrix with Gauss-Jordan elimination over GF(2^8),
+    /// returns `None` if the matrix is singular.
+    pub fn inverse(&self) -> Option<Matrix<N, M>> {
+        let mut a = *self;
+        let mut inverse = Matrix::identity();
+
+        for col in 0..N {
+            let pivot = (col..N).find(|&row| a.0[row][col] != BiPoly(0))?;
+            a.0.swap(col, pivot);
+            inverse.0.swap(col, pivot);
+
+            let factor = a.0[col][col].inverse()?;
+            for j in 0..N {
+                a.0[col][j] *= factor;
+                inverse.0[col][j] *= factor;
+            }
+
+            for row in 0..N {
+                let factor = a.0[row][col];
+                if row == col || factor == BiPoly(0) {
+                    continue;
+                }
+                for j in 0..N {
+                    a.0[row][j] -= factor * a.0[col][j];
+                    inverse.0[row][j] -= factor * inverse.0[col][j];
+                }
+            }
+        }
+        Some(inverse)
     }
 }
 
-impl std::fmt::Display for Matrix {
+impl<const N: usize, const M: u16> std::fmt::Display for Matrix<N, M> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
@@ -28,15 +146,15 @@ impl std::fmt::Display for Matrix {
     }
 }
 
-impl std::ops::Mul for Matrix {
-    type Output = Matrix;
+impl<const N: usize, const M: u16> std::ops::Mul for Matrix<N, M> {
+    type Output = Matrix<N, M>;
 
-    fn mul(self, rhs: Matrix) -> Self::Output {
+    fn mul(self, rhs: Matrix<N, M>) -> Self::Output {
         let mut out = Matrix::zeros();
 
-        for i in 0..4 {
-            for j in 0..4 {
-                for k in 0..4 {
+        for i in 0..N {
+            for j in 0..N {
+                for k in 0..N {
                     out.0[i][j] = self.0[i][k] * rhs.0[k][j] + out.0[i][j];
                 }
             }
@@ -46,16 +164,183 @@ impl std::ops::Mul for Matrix {
     }
 }
 
-impl std::ops::Add for Matrix {
-    type Output = Matrix;
+impl<const N: usize, const M: u16> std::ops::Add for Matrix<N, M> {
+    type Output = Matrix<N, M>;
 
-    fn add(self, rhs: Matrix) -> Self::Output {
+    fn add(self, rhs: Matrix<N, M>) -> Self::Output {
         let mut c = Matrix::zeros();
-        for row in 0..4 {
-            for col in 0..4 {
+        for row in 0..N {
+            for col in 0..N {
                 c.0[row][col] = self.0[row][col] + rhs.0[row][col];
             }
         }
         c
     }
 }
+
+impl<const N: usize, const M: u16> std::ops::Sub for Matrix<N, M> {
+    type Output = Matrix<N, M>;
+
+    /// Same as addition in characteristic 2
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn sub(self, rhs: Matrix<N, M>) -> Self::Output {
+        self + rhs
+    }
+}
+
+impl<const N: usize, const M: u16> std::ops::Mul<BiPoly<M>> for Matrix<N, M> {
+    type Output = Matrix<N, M>;
+
+    fn mul(self, rhs: BiPoly<M>) -> Self::Output {
+        let mut c = self;
+        for cell in c.0.iter_mut().flatten() {
+            *cell *= rhs;
+        }
+        c
+    }
+}
+
+impl<const N: usize, const M: u16> std::ops::Mul<Vector<N, M>> for Matrix<N, M> {
+    type Output = Vector<N, M>;
+
+    fn mul(self, rhs: Vector<N, M>) -> Self::Output {
+        let mut out = Vector([BiPoly(0); N]);
+        for i in 0..N {
+            for k in 0..N {
+                out.0[i] += self.0[i][k] * rhs.0[k];
+            }
+        }
+        out
+    }
+}
+
+/// The element in row `.0` and column `.1`
+impl<const N: usize, const M: u16> std::ops::Index<(usize, usize)> for Matrix<N, M> {
+    type Output = BiPoly<M>;
+
+    fn index(&self, (row, col): (usize, usize)) -> &Self::Output {
+        &self.0[row][col]
+    }
+}
+
+impl<const N: usize, const M: u16> std::ops::IndexMut<(usize, usize)> for Matrix<N, M> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut Self::Output {
+        &mut self.0[row][col]
+    }
+}
+
+impl<const N: usize, const M: u16> std::ops::Add for Vector<N, M> {
+    type Output = Vector<N, M>;
+
+    fn add(self, rhs: Vector<N, M>) -> Self::Output {
+        let mut c = self;
+        for (cell, &x) in c.0.iter_mut().zip(rhs.0.iter()) {
+            *cell += x;
+        }
+        c
+    }
+}
+
+impl<const N: usize, const M: u16> std::ops::Index<usize> for Vector<N, M> {
+    type Output = BiPoly<M>;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        &self.0[i]
+    }
+}
+
+impl<const N: usize, const M: u16> std::ops::IndexMut<usize> for Vector<N, M> {
+    fn index_mut(&mut self, i: usize) -> &mut Self::Output {
+        &mut self.0[i]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{ISO_C, ISO_MODULO, T};
+
+    fn singular() -> Matrix {
+        let mut singular = T;
+        // The last row becomes a multiple of the first
+        for j in 0..4 {
+            singular[(3, j)] = singular[(0, j)] * BiPoly(0x35);
+        }
+        singular
+    }
+
+    #[test]
+    fn test_inverse() {
+        let t_inverse = T.inverse().unwrap();
+        assert_eq!(T * t_inverse, Matrix::identity());
+        assert_eq!(t_inverse * T, Matrix::identity());
+        let c_inverse = ISO_C.inverse().unwrap();
+        assert_eq!(ISO_C * c_inverse, Matrix::identity());
+        assert_eq!(Matrix::<4>::identity().inverse(), Some(Matrix::identity()));
+
+        assert_eq!(singular().inverse(), None);
+        assert_eq!(Matrix::<4>::zeros().inverse(), None);
+    }
+
+    #[test]
+    fn test_determinant() {
+        assert_eq!(Matrix::<4>::identity().determinant(), BiPoly(1));
+        assert_eq!(Matrix::<4>::zeros().determinant(), BiPoly(0));
+        assert_eq!(singular().determinant(), BiPoly(0));
+        assert_ne!(T.determinant(), BiPoly(0));
+        assert_ne!(ISO_C.determinant(), BiPoly::<ISO_MODULO>(0));
+
+        let a = T * singular().transpose() + Matrix::identity();
+        assert_eq!((a * T).determinant(), a.determinant() * T.determinant());
+        assert_eq!(a.transpose().determinant(), a.determinant());
+        assert_eq!(
+            T.inverse().unwrap().determinant(),
+            T.determinant().inverse().unwrap()
+        );
+        // Scaling all four rows scales the determinant by the fourth power
+        assert_eq!(
+            (T * BiPoly(3)).determinant(),
+            T.determinant() * BiPoly(3).pow(4)
+        );
+    }
+
+    #[test]
+    fn test_circulant() {
+        assert_eq!(Matrix::circulant(T.0[0]), T);
+        assert_eq!(Matrix::circulant(ISO_C.0[0]), ISO_C);
+    }
+
+    #[test]
+    fn test_transpose() {
+        assert_eq!(T.transpose().transpose(), T);
+        assert_eq!(T.transpose()[(0, 1)], T[(1, 0)]);
+        assert_eq!(
+            (T * singular()).transpose(),
+            singular().transpose() * T.transpose()
+        );
+    }
+
+    #[test]
+    fn test_vector() {
+        let v = Vector([BiPoly(1), BiPoly(2), BiPoly(3), BiPoly(4)]);
+        assert_eq!(Matrix::identity() * v, v);
+        assert_eq!(T.inverse().unwrap() * (T * v), v);
+        assert_eq!(
+            (T * v)[0],
+            T[(0, 0)] + T[(0, 1)] * BiPoly(2) + T[(0, 2)] * BiPoly(3) + T[(0, 3)] * BiPoly(4)
+        );
+        assert_eq!(T * (v + v), Vector([BiPoly(0); 4]));
+        assert_eq!(T - T, Matrix::zeros());
+        assert_eq!(
+            Matrix::identity() * BiPoly(7) * v,
+            Vector(v.0.map(|x| x * BiPoly(7)))
+        );
+
+        let mut w = v;
+        w[2] = BiPoly(9);
+        assert_eq!(w.0, [BiPoly(1), BiPoly(2), BiPoly(9), BiPoly(4)]);
+        let mut m = T;
+        m[(2, 3)] = BiPoly(0xAB);
+        assert_eq!(m.0[2][3], BiPoly(0xAB));
+    }
+}
@@ -0,0 +1,259 @@
+/// What is appended to a message before it is split into blocks
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Padding {
+    /// Zero bytes, then the message length in bytes written big-endian into the last `size`
+    /// bytes of the block. mini-Whirlpool uses a 2 byte length.
+    Length { size: usize },
+    /// Merkle-Damgard strengthening: a single 1 bit, zero bits, then the message length in
+    /// bits written big-endian into the last `size` bytes. `size` 8 gives the usual 64-bit
+    /// length, the ISO Whirlpool uses 32.
+    Strengthened { size: usize },
+    /// Zero bytes up to the next block boundary, at least one block, and no length at all.
+    /// Messages that only differ in trailing zeros collide, so this is for research only.
+    ZeroPad,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PaddingError {
+    /// The message length does not fit into the length field of the padding scheme
+    MessageTooLong { length: usize, max_length: u128 },
+    /// The length field is empty, or the marker and the length field do not fit into a block
+    InvalidPadding { padding: Padding, block_size: usize },
+    /// The tail given to `pad_tail` is longer than the message, or does not start on a block
+    InvalidTail { tail: usize, length: usize },
+}
+
+impl std::fmt::Display for PaddingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaddingError::MessageTooLong { length, max_length } => write!(
+                f,
+                "message of {} bytes is longer than the padding allows ({} bytes)",
+                length, max_length
+            ),
+            PaddingError::InvalidPadding {
+                padding,
+                block_size,
+            } => write!(
+                f,
+                "padding {:?} cannot be used with blocks of {} bytes",
+                padding, block_size
+            ),
+            PaddingError::InvalidTail { tail, length } => write!(
+                f,
+                "{} bytes are not the tail of a message of {} bytes",
+                tail, length
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PaddingError {}
+
+impl Padding {
+    /// Bytes written after the message besides the zero fill: the marker and the length field
+    pub fn overhead(self) -> usize {
+        match self {
+            Padding::Length { size } => size,
+            Padding::Strengthened { size } => size + 1,
+            Padding::ZeroPad => 0,
+        }
+    }
+
+    /// The longest message in bytes whose length can be encoded, `None` if there is no limit
+    pub fn max_length(self) -> Option<u128> {
+        let (size, bits_per_byte) = match self {
+            Padding::Length { size } => (size, 1),
+            Padding::Strengthened { size } => (size, 8),
+            Padding::ZeroPad => return None,
+        };
+        if size >= std::mem::size_of::<u128>() {
+            return None;
+        }
+        Some(((1u128 << (8 * size)) - 1) / bits_per_byte)
+    }
+
+    /// Checks that the length field is not empty and that the padding fits into a block
+    pub fn validate(self, block_size: usize) -> Result<(), PaddingError> {
+        let empty_length = match self {
+            Padding::Length { size } | Padding::Strengthened { size } => size == 0,
+            Padding::ZeroPad => false,
+        };
+        if empty_length || block_size == 0 || self.overhead() > block_size {
+            return Err(PaddingError::InvalidPadding {
+                padding: self,
+                block_size,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn pad(self, input: &mut Vec<u8>, block_size: usize) -> Result<(), PaddingError> {
+        let payload_size = input.len();
+        self.pad_tail(input, payload_size, block_size)
+    }
+
+    /// Pads the not yet compressed tail of a message that is `payload_size` bytes long in
+    /// total. Everything before the tail must have been a whole number of blocks.
+    pub fn pad_tail(
+        self,
+        input: &mut Vec<u8>,
+        payload_size: usize,
+        block_size: usize,
+    ) -> Result<(), PaddingError> {
+        self.validate(block_size)?;
+        if input.len() > payload_size || (payload_size - input.len()) % block_size != 0 {
+            return Err(PaddingError::InvalidTail {
+                tail: input.len(),
+                length: payload_size,
+            });
+        }
+        if let Some(max_length) = self.max_length() {
+            if payload_size as u128 > max_length {
+                return Err(PaddingError::MessageTooLong {
+                    length: payload_size,
+                    max_length,
+                });
+            }
+        }
+
+        let blocks = match self {
+            Padding::ZeroPad => ((payload_size + block_size - 1) / block_size).max(1),
+            _ => (payload_size + self.overhead() - 1) / block_size + 1,
+        };
+        let bytes = blocks * block_size - (payload_size - input.len());
+        let (size, length) = match self {
+            Padding::Length { size } => (size, payload_size as u128),
+            Padding::Strengthened { size } => {
+                input.push(0b1000_0000);
+                (size, payload_size as u128 * 8)
+            }
+            Padding::ZeroPad => (0, 0),
+        };
+        input.resize(bytes, 0u8);
+
+        let length = length.to_be_bytes();
+        let written = size.min(length.len());
+        input[bytes - written..].copy_from_slice(&length[length.len() - written..]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDARIES: [usize; 7] = [14, 15, 16, 255, 256, 65535, 65536];
+
+    fn padded(padding: Padding, length: usize) -> Result<Vec<u8>, PaddingError> {
+        let mut input = vec![0xAA; length];
+        padding.pad(&mut input, 16).map(|_| input)
+    }
+
+    #[test]
+    fn test_length() {
+        let padding = Padding::Length { size: 2 };
+        let expected_sizes = [16, 32, 32, 272, 272, 65552];
+        for (&length, &size) in BOUNDARIES.iter().zip(expected_sizes.iter()) {
+            let input = padded(padding, length).unwrap();
+            assert_eq!(input.len(), size);
+            assert!(input[..length].iter().all(|&x| x == 0xAA));
+            assert!(input[length..size - 2].iter().all(|&x| x == 0));
+            assert_eq!(input[size - 2..], (length as u16).to_be_bytes());
+        }
+        assert_eq!(
+            padded(padding, 65536).unwrap_err(),
+            PaddingError::MessageTooLong {
+                length: 65536,
+                max_length: 65535
+            }
+        );
+    }
+
+    #[test]
+    fn test_strengthened() {
+        let padding = Padding::Strengthened { size: 8 };
+        let expected_sizes = [32, 32, 32, 272, 272, 65552, 65552];
+        for (&length, &size) in BOUNDARIES.iter().zip(expected_sizes.iter()) {
+            let input = padded(padding, length).unwrap();
+            assert_eq!(input.len(), size);
+            assert!(input[..length].iter().all(|&x| x == 0xAA));
+            assert_eq!(input[length], 0b1000_0000);
+            assert!(input[length + 1..size - 8].iter().all(|&x| x == 0));
+            assert_eq!(input[size - 8..], (8 * length as u64).to_be_bytes());
+        }
+        assert_eq!(padded(padding, 7).unwrap().len(), 16);
+        assert_eq!(padded(padding, 8).unwrap().len(), 32);
+        assert_eq!(padding.max_length(), Some(u64::MAX as u128 / 8));
+    }
+
+    #[test]
+    fn test_zero_pad() {
+        let expected_sizes = [16, 16, 16, 256, 256, 65536, 65536];
+        for (&length, &size) in BOUNDARIES.iter().zip(expected_sizes.iter()) {
+            let input = padded(Padding::ZeroPad, length).unwrap();
+            assert_eq!(input.len(), size);
+            assert!(input[length..].iter().all(|&x| x == 0));
+        }
+        assert_eq!(padded(Padding::ZeroPad, 0).unwrap(), vec![0; 16]);
+        assert_eq!(Padding::ZeroPad.max_length(), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        for &padding in [
+            Padding::Length { size: 0 },
+            Padding::Strengthened { size: 0 },
+        ]
+        .iter()
+        {
+            let invalid = PaddingError::InvalidPadding {
+                padding,
+                block_size: 16,
+            };
+            assert_eq!(padding.validate(16), Err(invalid.clone()));
+            assert_eq!(padded(padding, 0), Err(invalid.clone()));
+            assert_eq!(padded(padding, 1), Err(invalid));
+        }
+
+        // The length field may fill the block, with the marker one byte less
+        assert_eq!(Padding::Length { size: 16 }.validate(16), Ok(()));
+        assert!(Padding::Length { size: 17 }.validate(16).is_err());
+        assert_eq!(Padding::Strengthened { size: 15 }.validate(16), Ok(()));
+        assert!(Padding::Strengthened { size: 16 }.validate(16).is_err());
+        assert_eq!(
+            padded(Padding::Length { size: 16 }, 0).unwrap(),
+            vec![0; 16]
+        );
+        assert_eq!(Padding::Length { size: 1 }.validate(16), Ok(()));
+        assert_eq!(padded(Padding::Length { size: 1 }, 0).unwrap(), vec![0; 16]);
+        assert!(Padding::ZeroPad.validate(0).is_err());
+    }
+
+    #[test]
+    fn test_pad_tail() {
+        // Padding only the unprocessed tail gives the same bytes as padding everything
+        for &padding in [
+            Padding::Length { size: 2 },
+            Padding::Strengthened { size: 8 },
+            Padding::ZeroPad,
+        ]
+        .iter()
+        {
+            for &length in BOUNDARIES[..5].iter() {
+                let full = padded(padding, length).unwrap();
+                let mut tail = vec![0xAA; length % 16];
+                padding.pad_tail(&mut tail, length, 16).unwrap();
+                assert_eq!(tail[..], full[length - length % 16..]);
+            }
+
+            // The tail has to be the end of the message, after whole blocks
+            for &(tail, length) in [(5, 4), (17, 16), (5, 20), (0, 8)].iter() {
+                assert_eq!(
+                    padding.pad_tail(&mut vec![0xAA; tail], length, 16),
+                    Err(PaddingError::InvalidTail { tail, length })
+                );
+            }
+        }
+    }
+}
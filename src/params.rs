@@ -0,0 +1,403 @@
+use super::bipoly::BiPoly;
+use super::constants::*;
+use super::matrix::Matrix;
+use super::padding::Padding;
+use super::sbox::SBox;
+
+/// Everything that defines a whirlpool variant over an N x N state reduced modulo `M`:
+/// the S-box, the diffusion matrix, the round constants (one row per round), how the
+/// state is shifted and mixed, and how messages are padded.
+/// The block size is fixed by the state at N * N bytes.
+#[derive(Clone, Debug)]
+pub struct WhirlpoolParams<const N: usize = 4, const M: u16 = MODULO> {
+    pub(crate) sbox: SBox<M>,
+    pub(crate) t: Matrix<N, M>,
+    pub(crate) t_inverse: Matrix<N, M>,
+    pub(crate) round_constants: Vec<[BiPoly<M>; N]>,
+    pub(crate) layout: Layout,
+    pub(crate) padding: Padding,
+    /// MR in the last round, left out by AES but not by Whirlpool
+    pub(crate) final_mix: bool,
+    /// Adding the message and the chaining value to the cipher output
+    pub(crate) feed_forward: bool,
+    /// Deriving fresh round keys, without it every round adds the chaining value itself
+    pub(crate) key_schedule: bool,
+}
+
+/// How SC permutes the state and on which side MR applies the diffusion matrix
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Layout {
+    /// Row i is rotated left by i positions and MR computes T * state (mini-Whirlpool)
+    Rows,
+    /// Column j is rotated down by j positions and MR computes state * C (ISO Whirlpool)
+    Columns,
+}
+
+impl Layout {
+    /// Position in the input state that SC moves to position (i, j)
+    pub(crate) fn shift_source(self, i: usize, j: usize, n: usize) -> (usize, usize) {
+        match self {
+            Layout::Rows => (i, (j + i) % n),
+            Layout::Columns => ((i + n - j) % n, j),
+        }
+    }
+
+    pub(crate) fn mix<const N: usize, const M: u16>(
+        self,
+        t: Matrix<N, M>,
+        state: Matrix<N, M>,
+    ) -> Matrix<N, M> {
+        match self {
+            Layout::Rows => t * state,
+            Layout::Columns => state * t,
+        }
+    }
+}
+
+/// Weakened versions of a variant for cryptanalysis, see [`WhirlpoolParams::reduced`].
+/// The default keeps everything.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Reduction {
+    /// Only the first rounds, `None` for all of them
+    pub rounds: Option<usize>,
+    pub final_mix: bool,
+    pub feed_forward: bool,
+    pub key_schedule: bool,
+}
+
+impl Default for Reduction {
+    fn default() -> Self {
+        Reduction {
+            rounds: None,
+            final_mix: true,
+            feed_forward: true,
+            key_schedule: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParamsError {
+    /// The S-box maps two inputs to the same output
+    SBoxNotBijective,
+    /// The diffusion matrix has no inverse
+    SingularDiffusionMatrix,
+    /// No round constants were given, so the cipher would have no rounds
+    NoRounds,
+    /// More rounds were asked for than there are round constants
+    TooManyRounds { rounds: usize, available: usize },
+    /// The padding does not fit into a block or has an empty length field
+    InvalidPadding(Padding),
+}
+
+impl std::fmt::Display for ParamsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParamsError::SBoxNotBijective => write!(f, "S-box is not a bijection"),
+            ParamsError::SingularDiffusionMatrix => write!(f, "diffusion matrix is not invertible"),
+            ParamsError::NoRounds => write!(f, "at least one round is required"),
+            ParamsError::TooManyRounds { rounds, available } => write!(
+                f,
+                "{} rounds requested but only {} are defined",
+                rounds, available
+            ),
+            ParamsError::InvalidPadding(padding) => {
+                write!(f, "padding {:?} does not fit into a block", padding)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParamsError {}
+
+impl<const N: usize, const M: u16> WhirlpoolParams<N, M> {
+    pub fn new(
+        sbox: [[BiPoly<M>; 16]; 16],
+        t: Matrix<N, M>,
+        round_constants: Vec<[BiPoly<M>; N]>,
+        layout: Layout,
+        padding: Padding,
+    ) -> Result<WhirlpoolParams<N, M>, ParamsError> {
+        let sbox = SBox::new(sbox).ok_or(ParamsError::SBoxNotBijective)?;
+        let t_inverse = t.inverse().ok_or(ParamsError::SingularDiffusionMatrix)?;
+
+        if round_constants.is_empty() {
+            return Err(ParamsError::NoRounds);
+        }
+        padding
+            .validate(N * N)
+            .map_err(|_| ParamsError::InvalidPadding(padding))?;
+
+        Ok(WhirlpoolParams {
+            sbox,
+            t,
+            t_inverse,
+            round_constants,
+            layout,
+            padding,
+            final_mix: true,
+            feed_forward: true,
+            key_schedule: true,
+        })
+    }
+
+    /// Keeps only what `reduction` asks for: the first rounds, and optionally drops the final
+    /// MR, the feed-forward or the key schedule. Parts that are already gone stay gone.
+    pub fn reduced(mut self, reduction: Reduction) -> Result<WhirlpoolParams<N, M>, ParamsError> {
+        if let Some(rounds) = reduction.rounds {
+            if rounds == 0 {
+                return Err(ParamsError::NoRounds);
+            }
+            if rounds > self.rounds() {
+                return Err(ParamsError::TooManyRounds {
+                    rounds,
+                    available: self.rounds(),
+                });
+            }
+            self.round_constants.truncate(rounds);
+        }
+        self.final_mix &= reduction.final_mix;
+        self.feed_forward &= reduction.feed_forward;
+        self.key_schedule &= reduction.key_schedule;
+        Ok(self)
+    }
+
+    /// The same variant padded with another scheme, for example `Padding::Strengthened` to
+    /// hash messages longer than the 2 byte length field of mini-Whirlpool can describe
+    pub fn with_padding(mut self, padding: Padding) -> Result<WhirlpoolParams<N, M>, ParamsError> {
+        padding
+            .validate(N * N)
+            .map_err(|_| ParamsError::InvalidPadding(padding))?;
+        self.padding = padding;
+        Ok(self)
+    }
+
+    pub fn sbox(&self) -> &SBox<M> {
+        &self.sbox
+    }
+
+    pub fn t(&self) -> Matrix<N, M> {
+        self.t
+    }
+
+    pub fn round_constants(&self) -> &[[BiPoly<M>; N]] {
+        &self.round_constants
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.round_constants.len()
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    pub fn padding(&self) -> Padding {
+        self.padding
+    }
+
+    pub fn final_mix(&self) -> bool {
+        self.final_mix
+    }
+
+    pub fn feed_forward(&self) -> bool {
+        self.feed_forward
+    }
+
+    pub fn key_schedule(&self) -> bool {
+        self.key_schedule
+    }
+
+    pub fn block_size(&self) -> usize {
+        N * N
+    }
+}
+
+impl WhirlpoolParams<4, MODULO> {
+    /// The mini-Whirlpool variant defined in `constants`
+    pub fn mini() -> WhirlpoolParams<4, MODULO> {
+        WhirlpoolParams::new(
+            MATRIX,
+            T,
+            R.to_vec(),
+            Layout::Rows,
+            Padding::Length { size: LENGTH_SIZE },
+        )
+        .expect("mini-Whirlpool parameters are valid")
+    }
+}
+
+impl WhirlpoolParams<8, ISO_MODULO> {
+    /// The original 512-bit Whirlpool as standardised in ISO/IEC 10118-3.
+    /// The round constant of round r is the r-th run of eight consecutive S-box outputs.
+    pub fn iso() -> WhirlpoolParams<8, ISO_MODULO> {
+        let sbox: Vec<BiPoly<ISO_MODULO>> = ISO_MATRIX.iter().flatten().copied().collect();
+        let round_constants = sbox
+            .chunks(8)
+            .take(ISO_ROUNDS)
+            .map(|chunk| {
+                let mut constants = [BiPoly(0); 8];
+                constants.copy_from_slice(chunk);
+                constants
+            })
+            .collect();
+        WhirlpoolParams::new(
+            ISO_MATRIX,
+            ISO_C,
+            round_constants,
+            Layout::Columns,
+            Padding::Strengthened {
+                size: ISO_LENGTH_SIZE,
+            },
+        )
+        .expect("ISO Whirlpool parameters are valid")
+    }
+}
+
+impl Default for WhirlpoolParams {
+    fn default() -> Self {
+        WhirlpoolParams::mini()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mini() {
+        let params = WhirlpoolParams::mini();
+        assert_eq!(params.t * params.t_inverse, Matrix::identity());
+        assert_eq!(params.t_inverse * params.t, Matrix::identity());
+        assert_eq!(params.rounds(), 6);
+        assert_eq!(params.block_size(), BLOCK_SIZE);
+    }
+
+    #[test]
+    fn test_iso() {
+        let params = WhirlpoolParams::iso();
+        assert_eq!(params.rounds(), 10);
+        assert_eq!(params.block_size(), 64);
+        let first: Vec<u8> = params.round_constants[0].iter().map(|x| x.0).collect();
+        assert_eq!(first, [0x18, 0x23, 0xC6, 0xE8, 0x87, 0xB8, 0x01, 0x4F]);
+        let last: Vec<u8> = params.round_constants[9].iter().map(|x| x.0).collect();
+        assert_eq!(last, [0xCA, 0x2D, 0xBF, 0x07, 0xAD, 0x5A, 0x83, 0x33]);
+    }
+
+    #[test]
+    fn test_reduced() {
+        let params = WhirlpoolParams::mini()
+            .reduced(Reduction {
+                rounds: Some(2),
+                feed_forward: false,
+                ..Reduction::default()
+            })
+            .unwrap();
+        assert_eq!(params.rounds(), 2);
+        assert_eq!(params.round_constants(), &R[..2]);
+        assert!(params.final_mix() && params.key_schedule());
+        assert!(!params.feed_forward());
+
+        // Reducing again only takes more away
+        let params = params.reduced(Reduction::default()).unwrap();
+        assert_eq!(params.rounds(), 2);
+        assert!(!params.feed_forward());
+
+        let reduce = |rounds| {
+            WhirlpoolParams::mini().reduced(Reduction {
+                rounds: Some(rounds),
+                ..Reduction::default()
+            })
+        };
+        assert_eq!(reduce(0).unwrap_err(), ParamsError::NoRounds);
+        assert_eq!(
+            reduce(7).unwrap_err(),
+            ParamsError::TooManyRounds {
+                rounds: 7,
+                available: 6
+            }
+        );
+        assert_eq!(reduce(6).unwrap().rounds(), 6);
+    }
+
+    #[test]
+    fn test_validation() {
+        let mut sbox = MATRIX;
+        sbox[0][1] = sbox[0][0];
+        assert_eq!(
+            WhirlpoolParams::new(
+                sbox,
+                T,
+                R.to_vec(),
+                Layout::Rows,
+                Padding::Length { size: LENGTH_SIZE }
+            )
+            .unwrap_err(),
+            ParamsError::SBoxNotBijective
+        );
+
+        let mut t = T;
+        t.0[3] = t.0[0];
+        assert_eq!(
+            WhirlpoolParams::new(
+                MATRIX,
+                t,
+                R.to_vec(),
+                Layout::Rows,
+                Padding::Length { size: LENGTH_SIZE }
+            )
+            .unwrap_err(),
+            ParamsError::SingularDiffusionMatrix
+        );
+
+        assert_eq!(
+            WhirlpoolParams::new(
+                MATRIX,
+                T,
+                Vec::new(),
+                Layout::Rows,
+                Padding::Length { size: LENGTH_SIZE }
+            )
+            .unwrap_err(),
+            ParamsError::NoRounds
+        );
+        assert_eq!(
+            WhirlpoolParams::new(
+                MATRIX,
+                T,
+                R.to_vec(),
+                Layout::Rows,
+                Padding::Length { size: 17 }
+            )
+            .unwrap_err(),
+            ParamsError::InvalidPadding(Padding::Length { size: 17 })
+        );
+        for &padding in [
+            Padding::Length { size: 0 },
+            Padding::Strengthened { size: 0 },
+        ]
+        .iter()
+        {
+            assert_eq!(
+                WhirlpoolParams::new(MATRIX, T, R.to_vec(), Layout::Rows, padding).unwrap_err(),
+                ParamsError::InvalidPadding(padding)
+            );
+            assert_eq!(
+                WhirlpoolParams::mini().with_padding(padding).unwrap_err(),
+                ParamsError::InvalidPadding(padding)
+            );
+        }
+        let params = WhirlpoolParams::mini()
+            .with_padding(Padding::Strengthened { size: 8 })
+            .unwrap();
+        assert_eq!(params.padding(), Padding::Strengthened { size: 8 });
+        assert!(WhirlpoolParams::new(
+            MATRIX,
+            T,
+            R[..1].to_vec(),
+            Layout::Columns,
+            Padding::ZeroPad
+        )
+        .is_ok());
+    }
+}
@@ -1,22 +1,74 @@
-pub fn permutations<T>(universe: &[T], size: usize) -> PermutationIterator<T> {
-    PermutationIterator {
+use rayon::iter::plumbing::{bridge, Consumer, Producer, ProducerCallback, UnindexedConsumer};
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+use std::convert::TryFrom;
+use std::ops::Range;
+
+/// Every sequence of `size` elements of `universe`, repetitions allowed.
+/// The keyspace is ordered like a mixed-radix number whose least significant digit is the
+/// first element, so candidate `i` picks `universe[(i / n^k) % n]` at position `k`.
+///
+/// `None` if the number of candidates does not fit into `usize`, the type of the indexes.
+pub fn permutations<T>(universe: &[T], size: usize) -> Option<PermutationIterator<'_, T>> {
+    let end = if universe.is_empty() {
+        0
+    } else {
+        universe.len().checked_pow(u32::try_from(size).ok()?)?
+    };
+    Some(PermutationIterator {
         universe,
         size,
-        prev: None,
-    }
+        start: 0,
+        end,
+    })
 }
 
+/// Iterates over the keyspace indexes `start..end`
+#[derive(Clone, Debug)]
 pub struct PermutationIterator<'a, T: 'a> {
     universe: &'a [T],
     size: usize,
-    prev: Option<Vec<usize>>,
+    start: usize,
+    end: usize,
 }
 
-fn map<T>(values: &[T], ixs: &[usize]) -> Vec<T>
+impl<'a, T> PermutationIterator<'a, T>
 where
     T: Clone,
 {
-    ixs.iter().map(|&i| values[i].clone()).collect()
+    /// Decodes the `index`-th candidate of the whole keyspace, regardless of how far the
+    /// iterator has advanced
+    pub fn candidate_at(&self, index: usize) -> Vec<T> {
+        let n = self.universe.len();
+        let mut rest = index;
+        (0..self.size)
+            .map(|_| {
+                let value = self.universe[rest % n].clone();
+                rest /= n;
+                value
+            })
+            .collect()
+    }
+
+    /// Keyspace indexes that are still to be yielded
+    pub fn indexes(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Restricts the iterator to the keyspace indexes in `range`, which must lie within the
+    /// indexes that are left. This is how a part of the space is handed to a single worker.
+    pub fn with_range(self, range: Range<usize>) -> PermutationIterator<'a, T> {
+        assert!(
+            self.start <= range.start && range.start <= range.end && range.end <= self.end,
+            "Range {:?} is outside of the keyspace {:?}",
+            range,
+            self.indexes()
+        );
+        PermutationIterator {
+            start: range.start,
+            end: range.end,
+            ..self
+        }
+    }
 }
 
 impl<'a, T> Iterator for PermutationIterator<'a, T>
@@ -26,29 +78,210 @@ where
     type Item = Vec<T>;
 
     fn next(&mut self) -> Option<Vec<T>> {
-        let n = self.universe.len();
+        if self.start >= self.end {
+            return None;
+        }
+        let result = self.candidate_at(self.start);
+        self.start += 1;
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
 
-        if n == 0 {
+    fn nth(&mut self, n: usize) -> Option<Vec<T>> {
+        self.start = self.start.saturating_add(n).min(self.end);
+        self.next()
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for PermutationIterator<'a, T>
+where
+    T: Clone,
+{
+    fn next_back(&mut self) -> Option<Vec<T>> {
+        if self.start >= self.end {
             return None;
         }
+        self.end -= 1;
+        Some(self.candidate_at(self.end))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for PermutationIterator<'a, T> where T: Clone {}
+
+/// Splits the keyspace into contiguous index ranges, one per rayon job
+impl<'a, T> Producer for PermutationIterator<'a, T>
+where
+    T: Clone + Send + Sync,
+{
+    type Item = Vec<T>;
+    type IntoIter = PermutationIterator<'a, T>;
 
-        match self.prev {
-            None => {
-                let zeroes: Vec<usize> = std::iter::repeat(0).take(self.size).collect();
-                let result = Some(map(self.universe, &zeroes[..]));
-                self.prev = Some(zeroes);
-                result
-            }
-            Some(ref mut indexes) => match indexes.iter().position(|&i| i + 1 < n) {
-                None => None,
-                Some(position) => {
-                    for index in indexes.iter_mut().take(position) {
-                        *index = 0;
-                    }
-                    indexes[position] += 1;
-                    Some(map(self.universe, &indexes[..]))
-                }
+    fn into_iter(self) -> Self::IntoIter {
+        self
+    }
+
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let middle = self.start + index;
+        (
+            PermutationIterator {
+                end: middle,
+                ..self.clone()
+            },
+            PermutationIterator {
+                start: middle,
+                ..self
             },
+        )
+    }
+}
+
+/// Parallel version of `PermutationIterator`, created with `into_par_iter`
+pub struct ParPermutationIterator<'a, T: 'a> {
+    iter: PermutationIterator<'a, T>,
+}
+
+impl<'a, T> IntoParallelIterator for PermutationIterator<'a, T>
+where
+    T: Clone + Send + Sync,
+{
+    type Iter = ParPermutationIterator<'a, T>;
+    type Item = Vec<T>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        ParPermutationIterator { iter: self }
+    }
+}
+
+impl<'a, T> ParallelIterator for ParPermutationIterator<'a, T>
+where
+    T: Clone + Send + Sync,
+{
+    type Item = Vec<T>;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        bridge(self, consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+impl<'a, T> IndexedParallelIterator for ParPermutationIterator<'a, T>
+where
+    T: Clone + Send + Sync,
+{
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+
+    fn drive<C>(self, consumer: C) -> C::Result
+    where
+        C: Consumer<Self::Item>,
+    {
+        bridge(self, consumer)
+    }
+
+    fn with_producer<CB>(self, callback: CB) -> CB::Output
+    where
+        CB: ProducerCallback<Self::Item>,
+    {
+        callback.callback(self.iter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNIVERSE: &[u8] = b"abc";
+
+    #[test]
+    fn test_order() {
+        let expected: Vec<Vec<u8>> = (0..27)
+            .map(|i| vec![UNIVERSE[i % 3], UNIVERSE[i / 3 % 3], UNIVERSE[i / 9]])
+            .collect();
+        let all: Vec<Vec<u8>> = permutations(UNIVERSE, 3).unwrap().collect();
+        assert_eq!(all, expected);
+
+        let iter = permutations(UNIVERSE, 3).unwrap();
+        for (i, candidate) in expected.iter().enumerate() {
+            assert_eq!(&iter.candidate_at(i), candidate);
         }
+        let reversed: Vec<Vec<u8>> = permutations(UNIVERSE, 3).unwrap().rev().collect();
+        assert_eq!(reversed, expected.into_iter().rev().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        assert_eq!(
+            permutations(UNIVERSE, 0).unwrap().collect::<Vec<_>>(),
+            [vec![]]
+        );
+        assert_eq!(permutations(&[] as &[u8], 2).unwrap().next(), None);
+        assert_eq!(permutations(&[] as &[u8], 0).unwrap().len(), 0);
+        // 3^41 is more than 2^64, a keyspace that large cannot be indexed
+        assert!(permutations(UNIVERSE, 41).is_none());
+        assert!(permutations(UNIVERSE, usize::MAX).is_none());
+        assert!(permutations(&[] as &[u8], 41).is_some());
+    }
+
+    #[test]
+    fn test_len_and_nth() {
+        let mut iter = permutations(UNIVERSE, 3).unwrap();
+        assert_eq!(iter.len(), 27);
+        assert_eq!(iter.size_hint(), (27, Some(27)));
+        assert_eq!(iter.nth(4), Some(b"bba".to_vec()));
+        assert_eq!(iter.len(), 22);
+        assert_eq!(iter.next_back(), Some(b"ccc".to_vec()));
+        assert_eq!(iter.indexes(), 5..26);
+        assert_eq!(iter.nth(100), None);
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_with_range() {
+        let iter = permutations(UNIVERSE, 3).unwrap().with_range(10..13);
+        assert_eq!(
+            iter.collect::<Vec<_>>(),
+            [b"bab".to_vec(), b"cab".to_vec(), b"abb".to_vec()]
+        );
+        let (left, right) = permutations(UNIVERSE, 3)
+            .unwrap()
+            .with_range(10..13)
+            .split_at(1);
+        assert_eq!(left.indexes(), 10..11);
+        assert_eq!(right.indexes(), 11..13);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_with_range_outside() {
+        permutations(UNIVERSE, 3).unwrap().with_range(20..28);
+    }
+
+    #[test]
+    fn test_parallel() {
+        let sequential: Vec<Vec<u8>> = permutations(b"0123456789", 4).unwrap().collect();
+        let parallel: Vec<Vec<u8>> = permutations(b"0123456789", 4)
+            .unwrap()
+            .into_par_iter()
+            .collect();
+        assert_eq!(parallel, sequential);
+        let (index, _) = permutations(b"0123456789", 4)
+            .unwrap()
+            .into_par_iter()
+            .enumerate()
+            .find_any(|(_, x)| x == b"4321")
+            .unwrap();
+        assert_eq!(index, 1234);
     }
 }
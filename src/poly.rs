@@ -0,0 +1,279 @@
+use super::bipoly::BiPoly;
+use std::ops::{Add, Mul};
+
+/// Polynomial over Z_2 of degree below 64, bit i is the coefficient of x^i.
+/// Unlike `BiPoly` it is not reduced by anything, which makes it the tool to study the
+/// moduli themselves: which ones are irreducible or primitive and what generates the field.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Poly(pub u64);
+
+impl Poly {
+    pub const ZERO: Poly = Poly(0);
+    pub const ONE: Poly = Poly(1);
+    pub const X: Poly = Poly(2);
+
+    /// `None` for the zero polynomial
+    pub fn degree(self) -> Option<u32> {
+        if self.0 == 0 {
+            None
+        } else {
+            Some(63 - self.0.leading_zeros())
+        }
+    }
+
+    /// Quotient and remainder of the long division by `divisor`, panics if it is zero
+    pub fn divmod(self, divisor: Poly) -> (Poly, Poly) {
+        let divisor_degree = divisor.degree().expect("attempt to divide by zero");
+        let mut quotient = 0;
+        let mut remainder = self.0;
+        while let Some(degree) = Poly(remainder).degree() {
+            if degree < divisor_degree {
+                break;
+            }
+            let shift = degree - divisor_degree;
+            quotient |= 1 << shift;
+            remainder ^= divisor.0 << shift;
+        }
+        (Poly(quotient), Poly(remainder))
+    }
+
+    pub fn gcd(self, other: Poly) -> Poly {
+        let (mut a, mut b) = (self, other);
+        while b != Poly::ZERO {
+            let remainder = a.divmod(b).1;
+            a = b;
+            b = remainder;
+        }
+        a
+    }
+
+    /// `self * rhs` reduced modulo `modulus`, which can be of degree 32 at most
+    pub fn mul_mod(self, rhs: Poly, modulus: Poly) -> Poly {
+        (self.divmod(modulus).1 * rhs.divmod(modulus).1)
+            .divmod(modulus)
+            .1
+    }
+
+    /// `self^exp` reduced modulo `modulus`, by square and multiply
+    pub fn pow_mod(self, exp: u64, modulus: Poly) -> Poly {
+        let mut result = Poly::ONE.divmod(modulus).1;
+        let mut square = self;
+        let mut exp = exp;
+        while exp != 0 {
+            if exp & 1 == 1 {
+                result = result.mul_mod(square, modulus);
+            }
+            square = square.mul_mod(square, modulus);
+            exp >>= 1;
+        }
+        result
+    }
+
+    /// Rabin's test: f of degree n is irreducible exactly when x^(2^n) = x (mod f) and
+    /// x^(2^(n/q)) - x shares no factor with f for every prime q dividing n
+    pub fn is_irreducible(self) -> bool {
+        let n = match self.degree() {
+            Some(n) if n > 0 && n <= 32 => n,
+            _ => return false,
+        };
+        // x^(2^k) mod f, by squaring k times
+        let frobenius = |k: u32| (0..k).fold(Poly::X, |x, _| x.mul_mod(x, self));
+
+        if frobenius(n) != Poly::X.divmod(self).1 {
+            return false;
+        }
+        prime_factors(n as u64).into_iter().all(|q| {
+            let difference = frobenius(n / q as u32) + Poly::X;
+            self.gcd(difference) == Poly::ONE
+        })
+    }
+
+    /// An irreducible f of degree n is primitive when x has the full order 2^n - 1 modulo f,
+    /// so that the powers of x run through every non-zero element of the field
+    pub fn is_primitive(self) -> bool {
+        self.is_irreducible() && self.generates(Poly::X)
+    }
+
+    /// The smallest element of the field Z_2[x]/f whose powers give every non-zero element,
+    /// `None` if f is not irreducible
+    pub fn generator(self) -> Option<Poly> {
+        if !self.is_irreducible() {
+            return None;
+        }
+        let size = 1u64 << self.degree()?;
+        (2..size).map(Poly).find(|&g| self.generates(g))
+    }
+
+    /// Whether `element` has order 2^n - 1 modulo the irreducible f of degree n,
+    /// checked against the maximal proper divisors of 2^n - 1
+    fn generates(self, element: Poly) -> bool {
+        let order = (1u64 << self.degree().unwrap()) - 1;
+        element.divmod(self).1 != Poly::ZERO
+            && prime_factors(order)
+                .into_iter()
+                .all(|p| element.pow_mod(order / p, self) != Poly::ONE)
+    }
+}
+
+/// Every irreducible polynomial of the given degree, in increasing order
+pub fn irreducible_polynomials(degree: u32) -> Vec<Poly> {
+    assert!(degree <= 32, "Degree {} is too large", degree);
+    let first = 1u64 << degree;
+    (first..first << 1)
+        .map(Poly)
+        .filter(|x| x.is_irreducible())
+        .collect()
+}
+
+/// The smallest element of GF(2^8) modulo `M` that generates its multiplicative group
+pub fn generator<const M: u16>() -> BiPoly<M> {
+    let generator = Poly(M as u64)
+        .generator()
+        .expect("BiPoly only allows irreducible moduli");
+    BiPoly(generator.0 as u8)
+}
+
+/// The distinct prime factors of `n`, in increasing order
+fn prime_factors(mut n: u64) -> Vec<u64> {
+    let mut factors = Vec::new();
+    let mut p = 2;
+    while p * p <= n {
+        if n % p == 0 {
+            factors.push(p);
+            while n % p == 0 {
+                n /= p;
+            }
+        }
+        p += 1;
+    }
+    if n > 1 {
+        factors.push(n);
+    }
+    factors
+}
+
+impl std::fmt::Display for Poly {
+    /// Writes the terms from the highest degree down, for example `x^8 + x^4 + x^3 + x + 1`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let terms: Vec<String> = (0..64)
+            .rev()
+            .filter(|i| self.0 >> i & 1 == 1)
+            .map(|i| match i {
+                0 => "1".to_string(),
+                1 => "x".to_string(),
+                _ => format!("x^{}", i),
+            })
+            .collect();
+        if terms.is_empty() {
+            write!(f, "0")
+        } else {
+            write!(f, "{}", terms.join(" + "))
+        }
+    }
+}
+
+impl Add for Poly {
+    type Output = Poly;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn add(self, rhs: Poly) -> Poly {
+        Poly(self.0 ^ rhs.0)
+    }
+}
+
+impl Mul for Poly {
+    type Output = Poly;
+
+    /// Carry-less multiplication, panics if the product does not fit below degree 64
+    fn mul(self, rhs: Poly) -> Poly {
+        if let (Some(a), Some(b)) = (self.degree(), rhs.degree()) {
+            assert!(a + b < 64, "Product of {} and {} overflows", self, rhs);
+        }
+        let mut product = 0;
+        for i in 0..64 {
+            if rhs.0 >> i & 1 == 1 {
+                product ^= self.0 << i;
+            }
+        }
+        Poly(product)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{AES_MODULO, ISO_MODULO, MODULO};
+
+    #[test]
+    fn test_arithmetic() {
+        // (x + 1)(x^2 + x + 1) = x^3 + 1
+        assert_eq!(Poly(0b11) * Poly(0b111), Poly(0b1001));
+        assert_eq!(Poly(0b1001).divmod(Poly(0b11)), (Poly(0b111), Poly::ZERO));
+        assert_eq!(Poly(0b1011).divmod(Poly(0b11)), (Poly(0b110), Poly::ONE));
+        assert_eq!(Poly(0b1001).gcd(Poly(0b1111)), Poly(0b11));
+        assert_eq!(Poly::ZERO.degree(), None);
+        assert_eq!(Poly(MODULO as u64).degree(), Some(8));
+        assert_eq!(Poly(MODULO as u64).to_string(), "x^8 + x^5 + x^3 + x + 1");
+
+        for a in 0..256 {
+            for b in 1..64 {
+                let (quotient, remainder) = Poly(a).divmod(Poly(b));
+                assert_eq!(quotient * Poly(b) + remainder, Poly(a));
+                assert!(remainder.degree() < Poly(b).degree());
+            }
+        }
+    }
+
+    #[test]
+    fn test_mul_mod_matches_bipoly() {
+        for a in 0..=u8::MAX {
+            for b in 0..=u8::MAX {
+                let expected = (BiPoly::<MODULO>(a) * BiPoly(b)).0 as u64;
+                let product = Poly(a as u64).mul_mod(Poly(b as u64), Poly(MODULO as u64));
+                assert_eq!(product, Poly(expected));
+            }
+        }
+    }
+
+    #[test]
+    fn test_irreducible_polynomials() {
+        let irreducible = irreducible_polynomials(8);
+        assert_eq!(irreducible.len(), 30);
+        for modulus in [MODULO, ISO_MODULO, AES_MODULO].iter() {
+            assert!(irreducible.contains(&Poly(*modulus as u64)));
+        }
+        // Rabin's test agrees with trial division
+        for modulus in 0x100..0x200u16 {
+            assert_eq!(
+                Poly(modulus as u64).is_irreducible(),
+                crate::bipoly::is_irreducible(modulus)
+            );
+        }
+        // 2, 1, 2, 3, 6, 9 irreducible polynomials of degree 1 to 6
+        let counts: Vec<usize> = (1..=6).map(|n| irreducible_polynomials(n).len()).collect();
+        assert_eq!(counts, [2, 1, 2, 3, 6, 9]);
+    }
+
+    #[test]
+    fn test_primitive() {
+        // x generates the multiplicative group for 0x12B and 0x11D, but only has order 51
+        // for AES, where 0x03 is the usual generator instead
+        assert!(Poly(MODULO as u64).is_primitive());
+        assert!(Poly(ISO_MODULO as u64).is_primitive());
+        assert!(!Poly(AES_MODULO as u64).is_primitive());
+        assert_eq!(BiPoly::<AES_MODULO>(2).order(), Some(51));
+
+        assert_eq!(generator::<MODULO>(), BiPoly(0x02));
+        assert_eq!(generator::<ISO_MODULO>(), BiPoly(0x02));
+        assert_eq!(generator::<AES_MODULO>(), BiPoly(0x03));
+        assert_eq!(generator::<AES_MODULO>().order(), Some(255));
+
+        // 16 of the 30 irreducible polynomials of degree 8 are primitive
+        let primitive = irreducible_polynomials(8)
+            .into_iter()
+            .filter(|x| x.is_primitive())
+            .count();
+        assert_eq!(primitive, 16);
+        assert_eq!(Poly(0x101).generator(), None);
+    }
+}
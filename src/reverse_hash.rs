@@ -1,9 +1,60 @@
 use rayon::prelude::*;
-use std::time::Instant;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+use whirlpool::digest::Digest;
+use whirlpool::encoding::{from_hex, to_hex};
+use whirlpool::error::WhirlpoolError;
+use whirlpool::params::{Reduction, WhirlpoolParams};
 
 const CHARS: &[u8] =
     b"qwertyuiopasdfghjklzxcvbnmQWERTYUIOPASDFGHJKLZXCVBNM1234567890!@#%^-_=+([{<)]}>";
 
+const DEFAULT_MAX_LEN: usize = 4;
+
+/// Candidates hashed between two looks at the progress
+const CHUNK_SIZE: usize = 1 << 20;
+
+/// How often the checkpoint is written while nothing is found
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+const USAGE: &str = "Usage: reverse-hash [OPTIONS]
+
+Searches for preimages of mini-Whirlpool digests by trying every string over a charset.
+Without --target the built-in digests are searched, each at its known length.
+The reduced-round options search for digests of a weakened variant instead, which
+have to be given with --target.
+
+Options:
+    --target <HEX>        Digest to reverse, can be repeated
+    --target-file <FILE>  Read digests from FILE, one per line, # starts a comment
+    --charset <NAMES>     Comma separated presets: lower, upper, digits, symbols,
+                          printable, default (the default)
+    --chars <CHARS>       Use exactly these characters as the charset
+    --min-len <N>         Shortest candidate to try (default 1)
+    --max-len <N>         Longest candidate to try (default 4)
+    --threads <N>         Number of worker threads (default: one per core)
+    --reverse             Walk the charset back to front
+    --all                 Scan every length completely and list all preimages
+    --rounds <N>          Hash with only the first N of the 6 rounds
+    --no-final-mix        Leave out MR in the last round
+    --no-feed-forward     Leave out the feed-forward of the compression function
+    --no-key-schedule     Use the chaining value as every round key
+    --checkpoint <FILE>   Save the progress to FILE every 30 seconds and on every match
+    --resume              Continue the search saved in the --checkpoint file
+    -h, --help            Print this help
+
+Exit status:
+    0  every target has a preimage
+    1  some target has no preimage over the charset and lengths searched
+    2  the command line is wrong
+    3  the reduced-round options give invalid parameters
+    4  a candidate is longer than the padding allows
+    6  the checkpoint could not be read or written";
+
 const HASHES: &[(usize, [u8; 16])] = &[
     (
         2,
@@ -42,45 +93,1073 @@ const HASHES: &[(usize, [u8; 16])] = &[
     ),
 ];
 
-fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    let reverse_order = match args.get(1) {
-        Some(x) => x == "--reverse",
-        None => false,
+/// A digest to reverse together with the candidate lengths to try
+#[derive(Clone, Debug, PartialEq)]
+struct Target {
+    digest: Digest,
+    min_len: usize,
+    max_len: usize,
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    targets: Vec<Target>,
+    charset: Vec<u8>,
+    threads: Option<usize>,
+    reverse_order: bool,
+    find_all: bool,
+    reduction: Reduction,
+    checkpoint: Option<PathBuf>,
+    resume: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Search(Options),
+    Help,
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Command::Search(options)) => options,
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("reverse-hash: {}", message);
+            eprintln!("Try 'reverse-hash --help' for more information.");
+            return ExitCode::from(2);
+        }
     };
 
-    HASHES
-        .iter()
-        .for_each(|x| brute_force(reverse_order, x.0, x.1));
+    if let Some(threads) = options.threads {
+        if let Err(e) = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+        {
+            eprintln!("reverse-hash: {}", e);
+            return ExitCode::from(2);
+        }
+    }
+
+    match run(options) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("reverse-hash: {}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
 }
 
-fn brute_force(reverse_order: bool, chars_count: usize, expected: [u8; 16]) {
+/// Runs the search and prints what was found, returns whether every target has a preimage
+fn run(options: Options) -> Result<bool, WhirlpoolError> {
+    let path = options.checkpoint;
+    let mut checkpoint = match &path {
+        Some(path) if options.resume => Checkpoint::load(path)?,
+        Some(path) if path.exists() => {
+            return Err(io_error(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "checkpoint '{}' already exists, pass --resume to continue it",
+                    path.display()
+                ),
+            ))
+        }
+        _ => {
+            let mut charset = options.charset;
+            if options.reverse_order {
+                charset.reverse();
+            }
+            Checkpoint::new(
+                options.targets,
+                charset,
+                options.find_all,
+                options.reduction,
+            )
+        }
+    };
+
+    let params = WhirlpoolParams::mini().reduced(checkpoint.reduction)?;
     let start = Instant::now();
-    let mut chars = [0u8; 79];
-    chars.copy_from_slice(&CHARS[..]);
-    if reverse_order {
-        chars.reverse();
+    if !checkpoint.find_all {
+        for (target, found) in checkpoint.targets.iter().zip(checkpoint.found.iter()) {
+            if let Some(&(chars_count, index)) = found.first() {
+                println!(
+                    "Found the preimage for hash {}. It is {:?}. Found before resuming",
+                    target.digest,
+                    String::from_utf8_lossy(&checkpoint.candidate(chars_count, index))
+                );
+            }
+        }
     }
 
-    let p = whirlpool::permutations::permutations(&chars[..], chars_count)
-        .par_bridge()
-        .find_any(|p| {
-            if expected == whirlpool::core::hash(p.clone()) {
-                true
-            } else {
-                false
+    let mut last_save = Instant::now();
+    search(&params, &mut checkpoint, CHUNK_SIZE, |checkpoint, found| {
+        if !checkpoint.find_all {
+            for (i, preimage) in found {
+                println!(
+                    "Found the preimage for hash {}. It is {:?}. Took {:?}",
+                    checkpoint.targets[*i].digest,
+                    String::from_utf8_lossy(preimage),
+                    start.elapsed()
+                );
             }
-        });
-    match p {
-        None => panic!("Did not find any input"),
-        Some(x) => {
-            let duration = start.elapsed();
+        }
+        match &path {
+            Some(path) if !found.is_empty() || last_save.elapsed() >= SAVE_INTERVAL => {
+                last_save = Instant::now();
+                checkpoint.save(path)
+            }
+            _ => Ok(()),
+        }
+    })?;
+    if let Some(path) = &path {
+        checkpoint.save(path)?;
+    }
+
+    if checkpoint.find_all {
+        report_all(&checkpoint);
+        println!("Took {:?}", start.elapsed());
+    } else {
+        for (target, found) in checkpoint.targets.iter().zip(checkpoint.found.iter()) {
+            if found.is_empty() {
+                eprintln!(
+                    "No preimage for hash {} of length {} to {} over {} characters",
+                    target.digest,
+                    target.min_len,
+                    target.max_len,
+                    checkpoint.charset.len()
+                );
+            }
+        }
+    }
+    Ok(checkpoint.found.iter().all(|x| !x.is_empty()))
+}
+
+/// Prints how many preimages every target has at each length, next to the number a random
+/// function would give on average: charset size to the power of the length over 2^128.
+fn report_all(checkpoint: &Checkpoint) {
+    let charset_len = checkpoint.charset.len();
+    for (target, found) in checkpoint.targets.iter().zip(checkpoint.found.iter()) {
+        for chars_count in target.min_len..=target.max_len {
+            let preimages: Vec<Vec<u8>> = found
+                .iter()
+                .filter(|x| x.0 == chars_count)
+                .map(|&(chars_count, index)| checkpoint.candidate(chars_count, index))
+                .collect();
+            let expected = (charset_len as f64).powi(chars_count as i32) / 2f64.powi(128);
             println!(
-                "Found the preimage for hash {:?}. It is {:?}. Took {:?}",
-                expected,
-                String::from_utf8(x.clone()),
-                duration
+                "{} preimages of length {} for hash {} over {} characters ({:.3e} expected)",
+                preimages.len(),
+                chars_count,
+                target.digest,
+                charset_len,
+                expected
             );
+            for preimage in preimages {
+                println!("    {:?}", String::from_utf8_lossy(&preimage));
+            }
+        }
+    }
+}
+
+/// Searches all targets at once: every candidate is hashed a single time and looked up in the
+/// set of digests still missing at its length. Lengths are tried from the shortest up and the
+/// keyspace of each length is walked in chunks of `chunk_size` indexes, skipping whatever the
+/// checkpoint already covers.
+///
+/// After every chunk the matches are recorded in `checkpoint`, the chunk is marked as covered
+/// and `after_chunk` gets the new (target, preimage) pairs. Unless `find_all` is set a target
+/// drops out with its first preimage in keyspace order and the search ends when none are left.
+fn search<F>(
+    params: &WhirlpoolParams,
+    checkpoint: &mut Checkpoint,
+    chunk_size: usize,
+    mut after_chunk: F,
+) -> Result<(), WhirlpoolError>
+where
+    F: FnMut(&Checkpoint, &[(usize, Vec<u8>)]) -> Result<(), WhirlpoolError>,
+{
+    let charset = checkpoint.charset.clone();
+    for chars_count in lengths(&checkpoint.targets) {
+        let keyspace = whirlpool::permutations::permutations(&charset, chars_count)
+            .expect("the keyspace was checked when the targets were parsed");
+        for range in checkpoint.uncovered(chars_count, keyspace.indexes()) {
+            let mut start = range.start;
+            while start < range.end {
+                if (0..checkpoint.targets.len()).all(|i| checkpoint.is_done(i)) {
+                    return Ok(());
+                }
+                let pending = pending(&checkpoint.targets, chars_count, |i| !checkpoint.is_done(i));
+                if pending.is_empty() {
+                    break;
+                }
+
+                let end = start + chunk_size.min(range.end - start);
+                // Collecting an indexed parallel iterator keeps the keyspace order
+                let matches: Vec<(usize, Digest)> = keyspace
+                    .clone()
+                    .with_range(start..end)
+                    .into_par_iter()
+                    .enumerate()
+                    .filter_map(|(offset, p)| match digest(params, &p) {
+                        Ok(digest) if pending.contains_key(&digest) => {
+                            Some(Ok((start + offset, digest)))
+                        }
+                        Ok(_) => None,
+                        Err(e) => Some(Err(e)),
+                    })
+                    .collect::<Result<_, _>>()?;
+
+                let mut found = Vec::new();
+                for (index, digest) in matches {
+                    for &i in pending[&digest].iter() {
+                        if !checkpoint.is_done(i) {
+                            checkpoint.found[i].push((chars_count, index));
+                            found.push((i, keyspace.candidate_at(index)));
+                        }
+                    }
+                }
+                checkpoint.cover(chars_count, start..end);
+                after_chunk(checkpoint, &found)?;
+                start = end;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn digest(params: &WhirlpoolParams, candidate: &[u8]) -> Result<Digest, WhirlpoolError> {
+    whirlpool::core::hash(params, candidate.to_vec())
+}
+
+/// Every candidate length needed by at least one target
+fn lengths(targets: &[Target]) -> std::ops::RangeInclusive<usize> {
+    let min_len = targets.iter().map(|x| x.min_len).min().unwrap_or(1);
+    let max_len = targets.iter().map(|x| x.max_len).max().unwrap_or(0);
+    min_len..=max_len
+}
+
+/// Maps the digests to search for at `chars_count` to the indexes of the targets they belong
+/// to, a digest can be listed more than once with different lengths
+fn pending(
+    targets: &[Target],
+    chars_count: usize,
+    include: impl Fn(usize) -> bool,
+) -> HashMap<Digest, Vec<usize>> {
+    let mut pending: HashMap<Digest, Vec<usize>> = HashMap::new();
+    for (i, target) in targets.iter().enumerate() {
+        if include(i) && (target.min_len..=target.max_len).contains(&chars_count) {
+            pending.entry(target.digest.clone()).or_default().push(i);
+        }
+    }
+    pending
+}
+
+/// Everything needed to continue a search: what is searched and how far it got.
+/// Saved as a line based text file so that it can be inspected by hand, the `rounds` and
+/// `skip` lines are only there for a reduced variant:
+///
+/// ```text
+/// reverse-hash checkpoint
+/// charset <hex>
+/// mode first|all
+/// rounds <n>
+/// skip final-mix|feed-forward|key-schedule
+/// target <digest> <min-len> <max-len>
+/// covered <length> <start> <end>
+/// found <target> <length> <index>
+/// ```
+#[derive(Debug, PartialEq)]
+struct Checkpoint {
+    targets: Vec<Target>,
+    charset: Vec<u8>,
+    find_all: bool,
+    /// Which variant the digests belong to
+    reduction: Reduction,
+    /// Keyspace index ranges searched completely, sorted and merged, per candidate length
+    covered: BTreeMap<usize, Vec<Range<usize>>>,
+    /// Length and keyspace index of every preimage found so far, per target
+    found: Vec<Vec<(usize, usize)>>,
+}
+
+const CHECKPOINT_HEADER: &str = "reverse-hash checkpoint";
+
+impl Checkpoint {
+    fn new(
+        targets: Vec<Target>,
+        charset: Vec<u8>,
+        find_all: bool,
+        reduction: Reduction,
+    ) -> Checkpoint {
+        Checkpoint {
+            found: vec![Vec::new(); targets.len()],
+            targets,
+            charset,
+            find_all,
+            reduction,
+            covered: BTreeMap::new(),
+        }
+    }
+
+    /// A target is done once it has a preimage, unless all of them are wanted
+    fn is_done(&self, target: usize) -> bool {
+        !self.find_all && !self.found[target].is_empty()
+    }
+
+    fn candidate(&self, chars_count: usize, index: usize) -> Vec<u8> {
+        whirlpool::permutations::permutations(&self.charset, chars_count)
+            .expect("the keyspace was checked when the targets were parsed")
+            .candidate_at(index)
+    }
+
+    fn cover(&mut self, chars_count: usize, range: Range<usize>) {
+        let ranges = self.covered.entry(chars_count).or_default();
+        ranges.push(range);
+        ranges.sort_by_key(|x| x.start);
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges.drain(..) {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        *ranges = merged;
+    }
+
+    /// The parts of `keyspace` that are not covered yet, in order
+    fn uncovered(&self, chars_count: usize, keyspace: Range<usize>) -> Vec<Range<usize>> {
+        let mut gaps = Vec::new();
+        let mut start = keyspace.start;
+        for range in self.covered.get(&chars_count).into_iter().flatten() {
+            if range.start > start {
+                gaps.push(start..range.start.min(keyspace.end));
+            }
+            start = start.max(range.end);
+        }
+        if start < keyspace.end {
+            gaps.push(start..keyspace.end);
+        }
+        gaps.retain(|x| !x.is_empty());
+        gaps
+    }
+
+    fn load(path: &Path) -> Result<Checkpoint, WhirlpoolError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| io_error(e.kind(), format!("cannot read '{}': {}", path.display(), e)))?;
+        Checkpoint::parse(&contents).map_err(|e| {
+            io_error(
+                io::ErrorKind::InvalidData,
+                format!("invalid checkpoint '{}': {}", path.display(), e),
+            )
+        })
+    }
+
+    /// Writes a temporary file first and renames it over `path`, so a crash while saving
+    /// leaves the previous checkpoint intact
+    fn save(&self, path: &Path) -> Result<(), WhirlpoolError> {
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        std::fs::write(&temporary, self.to_string())
+            .and_then(|_| std::fs::rename(&temporary, path))
+            .map_err(|e| {
+                io_error(
+                    e.kind(),
+                    format!("cannot write '{}': {}", path.display(), e),
+                )
+            })
+    }
+
+    fn parse(contents: &str) -> Result<Checkpoint, String> {
+        let mut lines = contents.lines().enumerate();
+        if lines.next().map(|x| x.1) != Some(CHECKPOINT_HEADER) {
+            return Err("missing header".to_string());
+        }
+
+        let mut checkpoint = Checkpoint::new(Vec::new(), Vec::new(), false, Reduction::default());
+        let mut covered = Vec::new();
+        for (line_number, line) in lines {
+            let line_number = line_number + 1;
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let number = |i: usize| -> Result<usize, String> {
+                fields[i]
+                    .parse()
+                    .map_err(|_| format!("line {}: invalid number '{}'", line_number, fields[i]))
+            };
+            match fields[..] {
+                ["charset", hex] => {
+                    checkpoint.charset = from_hex(hex)
+                        .map_err(|e| format!("line {}: invalid charset: {}", line_number, e))?
+                }
+                ["mode", "first"] => checkpoint.find_all = false,
+                ["mode", "all"] => checkpoint.find_all = true,
+                ["rounds", _] => checkpoint.reduction.rounds = Some(number(1)?),
+                ["skip", "final-mix"] => checkpoint.reduction.final_mix = false,
+                ["skip", "feed-forward"] => checkpoint.reduction.feed_forward = false,
+                ["skip", "key-schedule"] => checkpoint.reduction.key_schedule = false,
+                ["target", hex, _, _] => {
+                    checkpoint.targets.push(Target {
+                        digest: parse_digest(hex)?,
+                        min_len: number(2)?,
+                        max_len: number(3)?,
+                    });
+                    checkpoint.found.push(Vec::new());
+                }
+                ["covered", _, _, _] => covered.push((number(1)?, number(2)?..number(3)?)),
+                ["found", _, _, _] => {
+                    let target = number(1)?;
+                    let chars_count = number(2)?;
+                    let lengths = checkpoint
+                        .targets
+                        .get(target)
+                        .map(|x| x.min_len..=x.max_len)
+                        .ok_or_else(|| format!("line {}: unknown target", line_number))?;
+                    if !lengths.contains(&chars_count) {
+                        return Err(format!(
+                            "line {}: length {} is outside the lengths of the target",
+                            line_number, chars_count
+                        ));
+                    }
+                    checkpoint.found[target].push((chars_count, number(3)?));
+                }
+                [] => {}
+                _ => return Err(format!("line {}: unexpected '{}'", line_number, line)),
+            }
+        }
+
+        if checkpoint.charset.is_empty() {
+            return Err("the charset is empty".to_string());
+        }
+        check_keyspace(&checkpoint.charset, &checkpoint.targets)?;
+        for (chars_count, range) in covered {
+            checkpoint.cover(chars_count, range);
+        }
+        for found in checkpoint.found.iter_mut() {
+            found.sort_unstable();
+        }
+        Ok(checkpoint)
+    }
+}
+
+impl std::fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", CHECKPOINT_HEADER)?;
+        writeln!(f, "charset {}", to_hex(&self.charset))?;
+        writeln!(f, "mode {}", if self.find_all { "all" } else { "first" })?;
+        if let Some(rounds) = self.reduction.rounds {
+            writeln!(f, "rounds {}", rounds)?;
+        }
+        for (kept, name) in [
+            (self.reduction.final_mix, "final-mix"),
+            (self.reduction.feed_forward, "feed-forward"),
+            (self.reduction.key_schedule, "key-schedule"),
+        ]
+        .iter()
+        {
+            if !kept {
+                writeln!(f, "skip {}", name)?;
+            }
+        }
+        for target in self.targets.iter() {
+            writeln!(
+                f,
+                "target {} {} {}",
+                target.digest, target.min_len, target.max_len
+            )?;
+        }
+        for (chars_count, ranges) in self.covered.iter() {
+            for range in ranges {
+                writeln!(f, "covered {} {} {}", chars_count, range.start, range.end)?;
+            }
+        }
+        for (i, found) in self.found.iter().enumerate() {
+            for (chars_count, index) in found {
+                writeln!(f, "found {} {} {}", i, chars_count, index)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Command, String> {
+    let mut digests = Vec::new();
+    let mut charset: Option<Vec<u8>> = None;
+    let mut min_len = None;
+    let mut max_len = None;
+    let mut threads = None;
+    let mut reverse_order = false;
+    let mut find_all = false;
+    let mut reduction = Reduction::default();
+    let mut checkpoint = None;
+    let mut resume = false;
+    // With --resume everything about the search comes from the checkpoint
+    let mut search_option = None;
+
+    while let Some(arg) = args.next() {
+        // Both `--option value` and `--option=value` are accepted
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+        let mut value = || match inline_value {
+            Some(value) => Ok(value.to_string()),
+            None => args
+                .next()
+                .ok_or_else(|| format!("option '{}' requires a value", name)),
+        };
+
+        if search_option.is_none()
+            && [
+                "--reverse",
+                "--all",
+                "--target",
+                "--target-file",
+                "--charset",
+                "--chars",
+                "--min-len",
+                "--max-len",
+                "--rounds",
+                "--no-final-mix",
+                "--no-feed-forward",
+                "--no-key-schedule",
+            ]
+            .contains(&name.as_str())
+        {
+            search_option = Some(name.clone());
+        }
+
+        match name.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--reverse" => reverse_order = true,
+            "--all" => find_all = true,
+            "--target" => digests.push(parse_digest(&value()?)?),
+            "--target-file" => {
+                let path = value()?;
+                let contents = std::fs::read_to_string(&path)
+                    .map_err(|e| format!("cannot read '{}': {}", path, e))?;
+                digests.extend(parse_target_file(&contents)?);
+            }
+            "--charset" => charset = Some(parse_charset(&value()?)?),
+            "--chars" => charset = Some(dedup(value()?.as_bytes())),
+            "--min-len" => min_len = Some(parse_number(&name, &value()?)?),
+            "--max-len" => max_len = Some(parse_number(&name, &value()?)?),
+            "--rounds" => reduction.rounds = Some(parse_number(&name, &value()?)?),
+            "--no-final-mix" => reduction.final_mix = false,
+            "--no-feed-forward" => reduction.feed_forward = false,
+            "--no-key-schedule" => reduction.key_schedule = false,
+            "--threads" => match parse_number(&name, &value()?)? {
+                0 => return Err("option '--threads' must be at least 1".to_string()),
+                n => threads = Some(n),
+            },
+            "--checkpoint" => checkpoint = Some(PathBuf::from(value()?)),
+            "--resume" => resume = true,
+            _ => return Err(format!("unexpected argument '{}'", arg)),
+        }
+    }
+
+    if resume {
+        if checkpoint.is_none() {
+            return Err("--resume requires --checkpoint".to_string());
+        }
+        if let Some(name) = search_option {
+            return Err(format!(
+                "--resume continues the search from the checkpoint and cannot be combined with '{}'",
+                name
+            ));
+        }
+    }
+
+    let charset = charset.unwrap_or_else(|| CHARS.to_vec());
+    if charset.is_empty() {
+        return Err("the charset is empty".to_string());
+    }
+    if let (Some(min), Some(max)) = (min_len, max_len) {
+        if min > max {
+            return Err(format!(
+                "--min-len {} is greater than --max-len {}",
+                min, max
+            ));
+        }
+    }
+
+    if let Err(e) = WhirlpoolParams::mini().reduced(reduction) {
+        return Err(format!("invalid reduction: {}", e));
+    }
+    if digests.is_empty() && reduction != Reduction::default() {
+        return Err(
+            "the built-in digests belong to the full hash, a reduced variant needs --target"
+                .to_string(),
+        );
+    }
+
+    let targets: Vec<Target> = if digests.is_empty() {
+        // The built-in digests only need to be tried at their known length
+        HASHES
+            .iter()
+            .filter(|(len, _)| min_len.map_or(true, |min| min <= *len))
+            .filter(|(len, _)| max_len.map_or(true, |max| *len <= max))
+            .map(|&(len, ref digest)| Target {
+                digest: Digest::from(&digest[..]),
+                min_len: len,
+                max_len: len,
+            })
+            .collect()
+    } else {
+        let min_len = min_len.unwrap_or(1);
+        let max_len = max_len.unwrap_or_else(|| DEFAULT_MAX_LEN.max(min_len));
+        digests
+            .into_iter()
+            .map(|digest| Target {
+                digest,
+                min_len,
+                max_len,
+            })
+            .collect()
+    };
+    if targets.is_empty() {
+        return Err("no built-in digest has a length within --min-len..--max-len".to_string());
+    }
+
+    check_keyspace(&charset, &targets)?;
+
+    Ok(Command::Search(Options {
+        targets,
+        charset,
+        threads,
+        reverse_order,
+        find_all,
+        reduction,
+        checkpoint,
+        resume,
+    }))
+}
+
+/// Keyspace indexes are `usize`, so the longest candidates of every target have to be
+/// countable in one
+fn check_keyspace(charset: &[u8], targets: &[Target]) -> Result<(), String> {
+    for target in targets {
+        if whirlpool::permutations::permutations(charset, target.max_len).is_none() {
+            return Err(format!(
+                "candidates of length {} over {} characters exceed the keyspace that can be indexed",
+                target.max_len,
+                charset.len()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// An I/O error whose message names the file it is about
+fn io_error(kind: io::ErrorKind, message: String) -> WhirlpoolError {
+    WhirlpoolError::Io(io::Error::new(kind, message))
+}
+
+fn parse_number(name: &str, value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{}' for '{}'", value, name))
+}
+
+fn parse_digest(hex: &str) -> Result<Digest, String> {
+    let hex = hex.trim();
+    match hex.parse::<Digest>() {
+        Ok(digest) if digest.len() == 16 => Ok(digest),
+        _ => Err(format!("'{}' is not a 32 digit hex digest", hex)),
+    }
+}
+
+/// One digest per line, blank lines and everything after `#` are ignored
+fn parse_target_file(contents: &str) -> Result<Vec<Digest>, String> {
+    contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap().trim())
+        .filter(|line| !line.is_empty())
+        .map(parse_digest)
+        .collect()
+}
+
+fn parse_charset(names: &str) -> Result<Vec<u8>, String> {
+    let mut charset = Vec::new();
+    for name in names.split(',') {
+        match name.trim() {
+            "lower" => charset.extend(b'a'..=b'z'),
+            "upper" => charset.extend(b'A'..=b'Z'),
+            "digits" => charset.extend(b'0'..=b'9'),
+            "symbols" => charset.extend(CHARS.iter().filter(|c| !c.is_ascii_alphanumeric())),
+            "printable" => charset.extend(b' '..=b'~'),
+            "default" => charset.extend_from_slice(CHARS),
+            other => {
+                return Err(format!(
+                    "unknown charset '{}', expected lower, upper, digits, symbols, printable or default",
+                    other
+                ))
+            }
+        }
+    }
+    Ok(dedup(&charset))
+}
+
+/// Drops repeated characters so that no candidate is tried twice
+fn dedup(chars: &[u8]) -> Vec<u8> {
+    let mut seen = [false; 256];
+    chars
+        .iter()
+        .copied()
+        .filter(|&c| !std::mem::replace(&mut seen[c as usize], true))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        parse_args(args.iter().map(|x| x.to_string()))
+    }
+
+    fn options(args: &[&str]) -> Options {
+        match parse(args) {
+            Ok(Command::Search(options)) => options,
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_defaults() {
+        let options = options(&[]);
+        assert_eq!(options.charset, CHARS);
+        assert_eq!(options.targets.len(), HASHES.len());
+        assert_eq!(options.threads, None);
+        assert!(!options.reverse_order);
+        assert!(!options.find_all);
+
+        let options = self::options(&["--max-len", "4", "--reverse", "--all"]);
+        let lengths: Vec<usize> = options.targets.iter().map(|x| x.min_len).collect();
+        assert_eq!(lengths, [2, 3, 4]);
+        assert!(options.reverse_order);
+        assert!(options.find_all);
+
+        // Nothing to search for is a wrong command line, not a successful search
+        assert!(parse(&["--max-len", "1"]).is_err());
+        assert!(parse(&["--min-len", "7", "--max-len", "8"]).is_err());
+        let hex = "bd84e6fbc06a36735dbcbd5496317ab2";
+        assert!(parse(&["--min-len", "7", "--target", hex]).is_ok());
+    }
+
+    #[test]
+    fn test_targets() {
+        let hex = "bd84e6fbc06a36735dbcbd5496317ab2";
+        let options = options(&[
+            "--target",
+            hex,
+            "--target=4CC406236355C4C32E4DB586778452A1",
+            "--min-len=2",
+            "--threads",
+            "3",
+        ]);
+        assert_eq!(options.targets[0].digest.as_ref(), HASHES[0].1);
+        assert_eq!(options.targets[1].digest.as_ref(), HASHES[1].1);
+        assert_eq!(options.targets[1].min_len, 2);
+        assert_eq!(options.targets[1].max_len, DEFAULT_MAX_LEN);
+        assert_eq!(options.threads, Some(3));
+
+        let file = format!("# targets\n\n{}  # length 2\n", hex);
+        assert_eq!(parse_target_file(&file).unwrap(), [hex.parse().unwrap()]);
+        assert!(parse_digest(&hex[2..]).is_err());
+    }
+
+    #[test]
+    fn test_charset() {
+        assert_eq!(options(&["--charset", "digits"]).charset, b"0123456789");
+        assert_eq!(options(&["--charset=lower,upper,lower"]).charset.len(), 52);
+        assert_eq!(options(&["--charset", "printable"]).charset.len(), 95);
+        assert_eq!(options(&["--chars", "abca"]).charset, b"abc");
+    }
+
+    fn zero_pad_params() -> WhirlpoolParams {
+        WhirlpoolParams::new(
+            whirlpool::constants::MATRIX,
+            whirlpool::constants::T,
+            whirlpool::constants::R.to_vec(),
+            whirlpool::params::Layout::Rows,
+            whirlpool::padding::Padding::ZeroPad,
+        )
+        .unwrap()
+    }
+
+    /// Runs a whole search and decodes the preimages of every target
+    fn preimages(
+        params: &WhirlpoolParams,
+        charset: &[u8],
+        targets: &[Target],
+        find_all: bool,
+        chunk_size: usize,
+    ) -> Vec<Vec<Vec<u8>>> {
+        let mut checkpoint = Checkpoint::new(
+            targets.to_vec(),
+            charset.to_vec(),
+            find_all,
+            Reduction::default(),
+        );
+        search(params, &mut checkpoint, chunk_size, |_, _| Ok(())).unwrap();
+        checkpoint
+            .found
+            .iter()
+            .map(|found| {
+                found
+                    .iter()
+                    .map(|&(chars_count, index)| checkpoint.candidate(chars_count, index))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_first() {
+        let params = WhirlpoolParams::mini();
+        let target = |input: &[u8], min_len, max_len| Target {
+            digest: digest(&params, input).unwrap(),
+            min_len,
+            max_len,
+        };
+        let targets = [
+            target(b"42", 1, 3),
+            target(b"7", 1, 1),
+            target(b"x", 1, 2),
+            target(b"42", 2, 2),
+        ];
+        let expected: [&[&[u8]]; 4] = [&[b"42"], &[b"7"], &[], &[b"42"]];
+        for &chunk_size in [1, 7, CHUNK_SIZE].iter() {
+            assert_eq!(
+                preimages(&params, b"0123456789", &targets, false, chunk_size),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_find_all() {
+        // Without a length field trailing zeros do not change the digest, which gives
+        // every message ending in something other than zero a family of preimages
+        let params = zero_pad_params();
+        let targets = [
+            Target {
+                digest: digest(&params, b"a").unwrap(),
+                min_len: 0,
+                max_len: 3,
+            },
+            Target {
+                digest: digest(&params, b"\0a").unwrap(),
+                min_len: 2,
+                max_len: 3,
+            },
+        ];
+        let expected: [&[&[u8]]; 2] = [&[b"a", b"a\0", b"a\0\0"], &[b"\0a", b"\0a\0"]];
+        for &chunk_size in [1, 4, CHUNK_SIZE].iter() {
+            assert_eq!(
+                preimages(&params, b"\0ab", &targets, true, chunk_size),
+                expected
+            );
+        }
+
+        // In the first preimage mode the shortest one, then the first in keyspace order wins
+        let expected: [&[&[u8]]; 2] = [&[b"a"], &[b"\0a"]];
+        assert_eq!(preimages(&params, b"\0ab", &targets, false, 4), expected);
+    }
+
+    #[test]
+    fn test_reduced() {
+        let hex = "bd84e6fbc06a36735dbcbd5496317ab2";
+        let options = options(&["--target", hex, "--rounds=2", "--no-feed-forward"]);
+        assert_eq!(
+            options.reduction,
+            Reduction {
+                rounds: Some(2),
+                feed_forward: false,
+                ..Reduction::default()
+            }
+        );
+        assert!(parse(&["--rounds", "2"]).is_err());
+        assert!(parse(&["--target", hex, "--rounds", "0"]).is_err());
+        assert!(parse(&["--target", hex, "--rounds", "7"]).is_err());
+        assert!(parse(&["--checkpoint", "x", "--resume", "--no-key-schedule"]).is_err());
+
+        // The search hashes with the reduced variant
+        let params = WhirlpoolParams::mini().reduced(options.reduction).unwrap();
+        let targets = [Target {
+            digest: digest(&params, b"42").unwrap(),
+            min_len: 1,
+            max_len: 2,
+        }];
+        let expected: [&[&[u8]]; 1] = [&[b"42"]];
+        assert_eq!(
+            preimages(&params, b"0123456789", &targets, false, 7),
+            expected
+        );
+        assert_ne!(
+            targets[0].digest,
+            digest(&WhirlpoolParams::mini(), b"42").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_resume() {
+        let params = zero_pad_params();
+        let targets = vec![Target {
+            digest: digest(&params, b"b").unwrap(),
+            min_len: 1,
+            max_len: 4,
+        }];
+        let keyspace_size = 3 + 9 + 27 + 81;
+        let covered = |checkpoint: &Checkpoint| -> usize {
+            checkpoint.covered.values().flatten().map(|x| x.len()).sum()
+        };
+
+        // Every chunk has to cover indexes that were not covered before
+        let mut tested = 0;
+        let mut checkpoint = Checkpoint::new(
+            targets.clone(),
+            b"\0ab".to_vec(),
+            true,
+            Reduction::default(),
+        );
+        let interrupted = search(&params, &mut checkpoint, 5, |checkpoint, _| {
+            assert!(covered(checkpoint) > tested);
+            tested = covered(checkpoint);
+            if tested > 40 {
+                Err(io::Error::from(io::ErrorKind::Interrupted).into())
+            } else {
+                Ok(())
+            }
+        });
+        assert!(matches!(interrupted, Err(WhirlpoolError::Io(_))));
+
+        let mut resumed = Checkpoint::parse(&checkpoint.to_string()).unwrap();
+        assert_eq!(resumed, checkpoint);
+        search(&params, &mut resumed, 5, |checkpoint, _| {
+            assert!(covered(checkpoint) > tested);
+            tested = covered(checkpoint);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(tested, keyspace_size);
+
+        let mut uninterrupted =
+            Checkpoint::new(targets, b"\0ab".to_vec(), true, Reduction::default());
+        search(&params, &mut uninterrupted, 5, |_, _| Ok(())).unwrap();
+        assert_eq!(resumed, uninterrupted);
+        assert_eq!(resumed.found[0], [(1, 2), (2, 2), (3, 2), (4, 2)]);
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let mut checkpoint = Checkpoint::new(
+            vec![Target {
+                digest: Digest::from(&HASHES[0].1[..]),
+                min_len: 1,
+                max_len: 2,
+            }],
+            b" ab".to_vec(),
+            false,
+            Reduction::default(),
+        );
+        checkpoint.cover(2, 4..6);
+        checkpoint.cover(2, 0..2);
+        checkpoint.cover(2, 2..3);
+        checkpoint.found[0].push((2, 5));
+        assert_eq!(checkpoint.covered[&2], [0..3, 4..6]);
+        assert_eq!(checkpoint.uncovered(2, 0..9), [3..4, 6..9]);
+        assert_eq!(checkpoint.uncovered(2, 4..6), []);
+        assert_eq!(checkpoint.uncovered(1, 1..3).pop(), Some(1..3));
+
+        let saved = checkpoint.to_string();
+        assert_eq!(
+            saved,
+            "reverse-hash checkpoint\n\
+             charset 206162\n\
+             mode first\n\
+             target bd84e6fbc06a36735dbcbd5496317ab2 1 2\n\
+             covered 2 0 3\n\
+             covered 2 4 6\n\
+             found 0 2 5\n"
+        );
+        assert_eq!(Checkpoint::parse(&saved), Ok(checkpoint));
+
+        let reduced = Checkpoint::new(
+            Vec::new(),
+            b"a".to_vec(),
+            true,
+            Reduction {
+                rounds: Some(3),
+                final_mix: false,
+                key_schedule: false,
+                ..Reduction::default()
+            },
+        );
+        let saved = reduced.to_string();
+        assert_eq!(
+            saved,
+            "reverse-hash checkpoint\n\
+             charset 61\n\
+             mode all\n\
+             rounds 3\n\
+             skip final-mix\n\
+             skip key-schedule\n"
+        );
+        assert_eq!(Checkpoint::parse(&saved), Ok(reduced));
+
+        assert!(Checkpoint::parse("").is_err());
+        assert!(Checkpoint::parse("reverse-hash checkpoint\nmode first\n").is_err());
+        assert!(Checkpoint::parse("reverse-hash checkpoint\ncharset 61\nfound 0 1 1\n").is_err());
+        assert!(Checkpoint::parse("reverse-hash checkpoint\ncharset 61\ncovered 1 x 1\n").is_err());
+        assert!(Checkpoint::parse("reverse-hash checkpoint\ncharset 61\nmode some\n").is_err());
+        let target = "target bd84e6fbc06a36735dbcbd5496317ab2";
+        assert!(Checkpoint::parse(&format!(
+            "reverse-hash checkpoint\ncharset 6162\n{} 1 2\nfound 0 3 1\n",
+            target
+        ))
+        .is_err());
+        assert!(Checkpoint::parse(&format!(
+            "reverse-hash checkpoint\ncharset 6162\n{} 1 99\n",
+            target
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(parse(&["--help"]), Ok(Command::Help));
+        assert!(parse(&["--target"]).is_err());
+        assert!(parse(&["--target", "bd84"]).is_err());
+        assert!(parse(&["--target", "zz84e6fbc06a36735dbcbd5496317ab2"]).is_err());
+        assert!(parse(&["--charset", "emoji"]).is_err());
+        assert!(parse(&["--chars="]).is_err());
+        assert!(parse(&["--min-len", "5", "--max-len", "4"]).is_err());
+        assert!(parse(&["--threads", "0"]).is_err());
+        assert!(parse(&["--threads", "many"]).is_err());
+        assert!(parse(&["--frobnicate"]).is_err());
+        // 80 characters to the power of 11 no longer fit into a keyspace index
+        assert!(parse(&[
+            "--target",
+            "bd84e6fbc06a36735dbcbd5496317ab2",
+            "--max-len",
+            "10"
+        ])
+        .is_ok());
+        for max_len in ["11", "100"].iter() {
+            let error = parse(&[
+                "--target",
+                "bd84e6fbc06a36735dbcbd5496317ab2",
+                "--max-len",
+                max_len,
+            ])
+            .unwrap_err();
+            assert!(error.contains("exceed the keyspace"), "{}", error);
         }
+        assert!(parse(&["--resume"]).is_err());
+        assert!(parse(&["--checkpoint", "x", "--resume", "--all"]).is_err());
+        assert!(parse(&["--checkpoint", "x", "--resume", "--threads", "2"]).is_ok());
     }
 }
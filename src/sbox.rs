@@ -0,0 +1,418 @@
+use super::bipoly::BiPoly;
+use super::constants::{AES_MODULO, MODULO};
+use std::io::{self, Write};
+
+/// A bijective S-box over GF(2^8) modulo `M` together with its inverse, both as flat
+/// 256-entry lookup tables indexed by the input byte
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SBox<const M: u16 = MODULO> {
+    forward: [BiPoly<M>; 256],
+    inverse: [BiPoly<M>; 256],
+}
+
+impl<const M: u16> SBox<M> {
+    /// Takes the S-box in its 16x16 form, where the row is the high nibble of the input and
+    /// the column the low one. `None` if two inputs map to the same output.
+    pub fn new(sbox: [[BiPoly<M>; 16]; 16]) -> Option<SBox<M>> {
+        let mut forward = [BiPoly(0); 256];
+        for (cell, &output) in forward.iter_mut().zip(sbox.iter().flatten()) {
+            *cell = output;
+        }
+
+        let mut inverse = [BiPoly(0); 256];
+        let mut seen = [false; 256];
+        for (input, output) in forward.iter().enumerate() {
+            if std::mem::replace(&mut seen[output.0 as usize], true) {
+                return None;
+            }
+            inverse[output.0 as usize] = BiPoly(input as u8);
+        }
+        Some(SBox { forward, inverse })
+    }
+
+    /// SB on a single byte
+    pub fn apply(&self, x: BiPoly<M>) -> BiPoly<M> {
+        self.forward[x.0 as usize]
+    }
+
+    /// SB^-1 on a single byte, `invert(apply(x)) == x`
+    pub fn invert(&self, y: BiPoly<M>) -> BiPoly<M> {
+        self.inverse[y.0 as usize]
+    }
+
+    /// The S-box with its inputs and outputs swapped
+    pub fn inverse(&self) -> SBox<M> {
+        SBox {
+            forward: self.inverse,
+            inverse: self.forward,
+        }
+    }
+
+    /// The outputs as plain bytes, in input order, as taken by the analysis functions
+    pub fn to_bytes(&self) -> [u8; 256] {
+        to_bytes(self.forward.iter())
+    }
+
+    pub fn analyze(&self) -> Report {
+        analyze(&self.to_bytes())
+    }
+}
+
+/// The S-box as a flat lookup table, the row of the 16x16 form is the high nibble
+pub fn table<const M: u16>(sbox: &[[BiPoly<M>; 16]; 16]) -> [u8; 256] {
+    to_bytes(sbox.iter().flatten())
+}
+
+/// The first 256 outputs as plain bytes
+fn to_bytes<'a, const M: u16>(outputs: impl Iterator<Item = &'a BiPoly<M>>) -> [u8; 256] {
+    let mut table = [0u8; 256];
+    for (cell, output) in table.iter_mut().zip(outputs) {
+        *cell = output.0;
+    }
+    table
+}
+
+/// The AES S-box, inversion modulo `AES_MODULO` followed by the affine map, for comparison
+pub fn aes() -> [u8; 256] {
+    let mut table = [0u8; 256];
+    for (x, cell) in table.iter_mut().enumerate() {
+        let b = BiPoly::<AES_MODULO>(x as u8)
+            .inverse()
+            .unwrap_or(BiPoly(0))
+            .0;
+        *cell =
+            b ^ b.rotate_left(1) ^ b.rotate_left(2) ^ b.rotate_left(3) ^ b.rotate_left(4) ^ 0x63;
+    }
+    table
+}
+
+/// How well an S-box resists differential and linear cryptanalysis
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Report {
+    /// Largest entry of the DDT outside of the zero difference, 2 at best for 8 bits
+    pub differential_uniformity: u16,
+    /// Distance to the closest affine function over all non-zero output masks, 112 at best
+    pub nonlinearity: u16,
+    /// Algebraic degree of every output bit, least significant first, 7 at most for a bijection
+    pub degrees: [u32; 8],
+    pub fixed_points: Vec<u8>,
+    /// Cycle lengths of the permutation, longest first, `None` if the S-box is not bijective
+    pub cycles: Option<Vec<usize>>,
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "differential uniformity: {} (probability 2^{:.2})",
+            self.differential_uniformity,
+            (self.differential_uniformity as f64 / 256.0).log2()
+        )?;
+        writeln!(
+            f,
+            "nonlinearity: {} (largest LAT entry {})",
+            self.nonlinearity,
+            128 - self.nonlinearity
+        )?;
+        writeln!(f, "algebraic degree per output bit: {:?}", self.degrees)?;
+        let fixed_points: Vec<String> = self
+            .fixed_points
+            .iter()
+            .map(|x| format!("{:02x}", x))
+            .collect();
+        writeln!(
+            f,
+            "fixed points: {} [{}]",
+            fixed_points.len(),
+            fixed_points.join(", ")
+        )?;
+        match &self.cycles {
+            Some(cycles) => writeln!(f, "cycles: {} {:?}", cycles.len(), cycles),
+            None => writeln!(f, "cycles: not a permutation"),
+        }
+    }
+}
+
+pub fn analyze(sbox: &[u8; 256]) -> Report {
+    Report {
+        differential_uniformity: differential_uniformity(&ddt(sbox)),
+        nonlinearity: nonlinearity(&lat(sbox)),
+        degrees: algebraic_degrees(sbox),
+        fixed_points: fixed_points(sbox),
+        cycles: cycles(sbox),
+    }
+}
+
+/// Difference distribution table, entry `[a][b]` counts the inputs `x` with
+/// `S(x) ^ S(x ^ a) == b`
+pub fn ddt(sbox: &[u8; 256]) -> Vec<[u16; 256]> {
+    let mut table = vec![[0u16; 256]; 256];
+    for (a, row) in table.iter_mut().enumerate() {
+        for x in 0..256 {
+            row[(sbox[x] ^ sbox[x ^ a]) as usize] += 1;
+        }
+    }
+    table
+}
+
+/// Linear approximation table, entry `[a][b]` counts the inputs `x` with
+/// `a·x == b·S(x)` minus 128, so that an unbiased approximation is zero
+pub fn lat(sbox: &[u8; 256]) -> Vec<[i16; 256]> {
+    let mut table = vec![[0i16; 256]; 256];
+    for b in 0..=u8::MAX {
+        // The Walsh spectrum of the component function b·S, for every input mask at once
+        let mut spectrum = [0i32; 256];
+        for (x, value) in spectrum.iter_mut().enumerate() {
+            *value = if parity(b & sbox[x]) { -1 } else { 1 };
+        }
+        walsh_hadamard(&mut spectrum);
+        for (row, value) in table.iter_mut().zip(spectrum.iter()) {
+            row[b as usize] = (value / 2) as i16;
+        }
+    }
+    table
+}
+
+/// Largest DDT entry for a non-zero input difference
+pub fn differential_uniformity(ddt: &[[u16; 256]]) -> u16 {
+    ddt.iter().skip(1).flatten().copied().max().unwrap_or(0)
+}
+
+/// 128 minus the largest absolute LAT entry for a non-zero output mask
+pub fn nonlinearity(lat: &[[i16; 256]]) -> u16 {
+    let largest = lat
+        .iter()
+        .flat_map(|row| row.iter().skip(1))
+        .map(|x| x.unsigned_abs())
+        .max()
+        .unwrap_or(0);
+    128 - largest
+}
+
+/// Degree of the algebraic normal form of every output bit, least significant first.
+/// A constant zero bit has degree 0 like any other constant.
+pub fn algebraic_degrees(sbox: &[u8; 256]) -> [u32; 8] {
+    let mut degrees = [0; 8];
+    for (bit, degree) in degrees.iter_mut().enumerate() {
+        let mut anf = [0u8; 256];
+        for (x, coefficient) in anf.iter_mut().enumerate() {
+            *coefficient = sbox[x] >> bit & 1;
+        }
+        mobius(&mut anf);
+        *degree = (0..256u32)
+            .filter(|&monomial| anf[monomial as usize] == 1)
+            .map(u32::count_ones)
+            .max()
+            .unwrap_or(0);
+    }
+    degrees
+}
+
+pub fn fixed_points(sbox: &[u8; 256]) -> Vec<u8> {
+    (0..=u8::MAX).filter(|&x| sbox[x as usize] == x).collect()
+}
+
+/// Cycle lengths of the permutation, longest first, `None` if the S-box is not bijective
+pub fn cycles(sbox: &[u8; 256]) -> Option<Vec<usize>> {
+    let mut seen = [false; 256];
+    for &output in sbox.iter() {
+        if std::mem::replace(&mut seen[output as usize], true) {
+            return None;
+        }
+    }
+
+    let mut visited = [false; 256];
+    let mut cycles = Vec::new();
+    for start in 0..256 {
+        let mut length = 0;
+        let mut x = start;
+        while !visited[x] {
+            visited[x] = true;
+            x = sbox[x] as usize;
+            length += 1;
+        }
+        if length > 0 {
+            cycles.push(length);
+        }
+    }
+    cycles.sort_unstable_by(|a, b| b.cmp(a));
+    Some(cycles)
+}
+
+/// Writes a DDT or LAT as CSV, the first row holds the output differences or masks and the
+/// first column the input ones
+pub fn write_csv<T: std::fmt::Display, W: Write>(
+    table: &[[T; 256]],
+    mut writer: W,
+) -> io::Result<()> {
+    let header: Vec<String> = (0..256).map(|b| b.to_string()).collect();
+    writeln!(writer, "a\\b,{}", header.join(","))?;
+    for (a, row) in table.iter().enumerate() {
+        let cells: Vec<String> = row.iter().map(|x| x.to_string()).collect();
+        writeln!(writer, "{},{}", a, cells.join(","))?;
+    }
+    writer.flush()
+}
+
+fn parity(x: u8) -> bool {
+    x.count_ones() % 2 == 1
+}
+
+/// In-place fast Walsh–Hadamard transform
+fn walsh_hadamard(values: &mut [i32; 256]) {
+    let mut half = 1;
+    while half < 256 {
+        for block in (0..256).step_by(2 * half) {
+            for i in block..block + half {
+                let (a, b) = (values[i], values[i + half]);
+                values[i] = a + b;
+                values[i + half] = a - b;
+            }
+        }
+        half *= 2;
+    }
+}
+
+/// In-place Möbius transform, turns a truth table into the coefficients of its algebraic
+/// normal form, indexed by the monomial's variables
+fn mobius(values: &mut [u8; 256]) {
+    let mut half = 1;
+    while half < 256 {
+        for block in (0..256).step_by(2 * half) {
+            for i in block..block + half {
+                values[i + half] ^= values[i];
+            }
+        }
+        half *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{ISO_MATRIX, MATRIX};
+
+    #[test]
+    fn test_sbox() {
+        let sbox = SBox::new(MATRIX).unwrap();
+        for x in 0..=u8::MAX {
+            let x = BiPoly(x);
+            assert_eq!(sbox.invert(sbox.apply(x)), x);
+            assert_eq!(sbox.apply(sbox.invert(x)), x);
+            assert_eq!(sbox.inverse().apply(x), sbox.invert(x));
+        }
+        // The flat lookup agrees with the row and column of the 16x16 form
+        assert_eq!(sbox.apply(BiPoly(0x00)), MATRIX[0][0]);
+        assert_eq!(sbox.apply(BiPoly(0x3A)), MATRIX[3][10]);
+        assert_eq!(sbox.apply(BiPoly(0xFF)), MATRIX[15][15]);
+        assert_eq!(sbox.to_bytes(), table(&MATRIX));
+        assert_eq!(sbox.inverse().inverse(), sbox);
+        assert_eq!(sbox.analyze(), analyze(&table(&MATRIX)));
+
+        let iso = SBox::new(ISO_MATRIX).unwrap();
+        assert_eq!(iso.apply(BiPoly(0x00)), BiPoly(0x18));
+        assert_eq!(iso.invert(BiPoly(0x18)), BiPoly(0x00));
+
+        let mut repeated = MATRIX;
+        repeated[15][15] = repeated[0][0];
+        assert_eq!(SBox::new(repeated), None);
+    }
+
+    #[test]
+    fn test_aes() {
+        let sbox = aes();
+        assert_eq!(sbox[0x00], 0x63);
+        assert_eq!(sbox[0x01], 0x7c);
+        assert_eq!(sbox[0x53], 0xed);
+        assert_eq!(sbox[0xff], 0x16);
+
+        let report = analyze(&sbox);
+        assert_eq!(report.differential_uniformity, 4);
+        assert_eq!(report.nonlinearity, 112);
+        assert_eq!(report.degrees, [7; 8]);
+        assert!(report.fixed_points.is_empty());
+        assert_eq!(report.cycles, Some(vec![87, 81, 59, 27, 2]));
+    }
+
+    #[test]
+    fn test_iso() {
+        let report = analyze(&table(&ISO_MATRIX));
+        assert_eq!(report.differential_uniformity, 8);
+        assert_eq!(report.nonlinearity, 100);
+        assert_eq!(report.degrees, [7; 8]);
+        assert!(report.fixed_points.is_empty());
+        assert_eq!(
+            report.cycles,
+            Some(vec![92, 82, 20, 12, 12, 8, 8, 8, 6, 3, 3, 2])
+        );
+    }
+
+    #[test]
+    fn test_mini() {
+        // As good as the AES S-box, and a single cycle through all bytes
+        let report = analyze(&table(&MATRIX));
+        assert_eq!(report.differential_uniformity, 4);
+        assert_eq!(report.nonlinearity, 112);
+        assert_eq!(report.degrees, [7; 8]);
+        assert!(report.fixed_points.is_empty());
+        assert_eq!(report.cycles, Some(vec![256]));
+        assert!(report
+            .to_string()
+            .contains("differential uniformity: 4 (probability 2^-6.00)"));
+    }
+
+    #[test]
+    fn test_tables() {
+        let sbox = table(&MATRIX);
+        let ddt = ddt(&sbox);
+        let lat = lat(&sbox);
+        assert_eq!(ddt[0][0], 256);
+        assert_eq!(lat[0][0], 128);
+        for row in ddt.iter() {
+            // Every difference pairs up the inputs, the DDT rows sum to 256
+            assert_eq!(row.iter().map(|&x| x as u32).sum::<u32>(), 256);
+            assert!(row.iter().all(|x| x % 2 == 0));
+        }
+        // Spot check the Walsh transform against counting
+        for &(a, b) in [(1, 1), (0x35, 0xc4), (0xff, 0x80)].iter() {
+            let count = (0..256)
+                .filter(|&x| parity(a & x as u8) == parity(b & sbox[x]))
+                .count() as i16;
+            assert_eq!(lat[a as usize][b as usize], count - 128);
+        }
+    }
+
+    #[test]
+    fn test_degrees_and_cycles() {
+        let identity: Vec<u8> = (0..=u8::MAX).collect();
+        let identity: [u8; 256] = std::convert::TryFrom::try_from(identity).unwrap();
+        assert_eq!(algebraic_degrees(&identity), [1; 8]);
+        assert_eq!(fixed_points(&identity).len(), 256);
+        assert_eq!(cycles(&identity), Some(vec![1; 256]));
+        assert_eq!(nonlinearity(&lat(&identity)), 0);
+
+        // x -> x + 1 on the bytes has a single cycle
+        let mut shift = [0u8; 256];
+        for (x, cell) in shift.iter_mut().enumerate() {
+            *cell = (x as u8).wrapping_add(1);
+        }
+        assert_eq!(cycles(&shift), Some(vec![256]));
+
+        let constant = [7u8; 256];
+        assert_eq!(cycles(&constant), None);
+        assert_eq!(algebraic_degrees(&constant), [0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(differential_uniformity(&ddt(&constant)), 256);
+    }
+
+    #[test]
+    fn test_csv() {
+        let mut out = Vec::new();
+        write_csv(&ddt(&aes()), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 257);
+        assert!(lines[0].starts_with("a\\b,0,1,2,"));
+        assert!(lines[1].starts_with("0,256,0,0,"));
+        assert_eq!(lines[2].split(',').count(), 257);
+    }
+}
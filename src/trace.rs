@@ -0,0 +1,193 @@
+use super::constants::MODULO;
+use super::digest::Digest;
+use super::encoding::to_hex;
+use super::matrix::Matrix;
+
+/// One of the four steps of a round, named as in the Whirlpool specification
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Step {
+    AddKey,
+    SubBytes,
+    ShiftColumns,
+    MixRows,
+}
+
+impl std::fmt::Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Step::AddKey => "AK",
+            Step::SubBytes => "SB",
+            Step::ShiftColumns => "SC",
+            Step::MixRows => "MR",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The key and the data state right after `step` of `round`, where round 0 is the initial AK
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct State<const N: usize = 4, const M: u16 = MODULO> {
+    pub round: usize,
+    pub step: Step,
+    pub key: Matrix<N, M>,
+    pub data: Matrix<N, M>,
+}
+
+/// The compression of a single padded message block
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BlockTrace<const N: usize = 4, const M: u16 = MODULO> {
+    /// H_{i-1}, the key of the block cipher
+    pub chaining: Matrix<N, M>,
+    /// m_i, the plaintext of the block cipher
+    pub message: Matrix<N, M>,
+    pub states: Vec<State<N, M>>,
+    /// H_i = W_{H_{i-1}}(m_i) + m_i + H_{i-1}
+    pub output: Matrix<N, M>,
+}
+
+/// Every intermediate state of a hash computation, created by `core::hash_traced`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Trace<const N: usize = 4, const M: u16 = MODULO> {
+    pub blocks: Vec<BlockTrace<N, M>>,
+    pub digest: Digest,
+}
+
+impl<const N: usize, const M: u16> Trace<N, M> {
+    /// The trace as a single JSON object. Matrices are lists of rows, each row a hex string,
+    /// so that two dumps can be compared line by line once pretty printed.
+    pub fn to_json(&self) -> String {
+        let blocks: Vec<String> = self
+            .blocks
+            .iter()
+            .enumerate()
+            .map(|(i, block)| {
+                let states: Vec<String> = block
+                    .states
+                    .iter()
+                    .map(|state| {
+                        format!(
+                            "{{\"round\":{},\"step\":\"{}\",\"key\":{},\"data\":{}}}",
+                            state.round,
+                            state.step,
+                            json_matrix(&state.key),
+                            json_matrix(&state.data)
+                        )
+                    })
+                    .collect();
+                format!(
+                    "{{\"block\":{},\"chaining\":{},\"message\":{},\"states\":[{}],\"output\":{}}}",
+                    i,
+                    json_matrix(&block.chaining),
+                    json_matrix(&block.message),
+                    states.join(","),
+                    json_matrix(&block.output)
+                )
+            })
+            .collect();
+        format!(
+            "{{\"digest\":\"{}\",\"blocks\":[{}]}}",
+            self.digest,
+            blocks.join(",")
+        )
+    }
+}
+
+impl<const N: usize, const M: u16> std::fmt::Display for Trace<N, M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, block) in self.blocks.iter().enumerate() {
+            writeln!(f, "block {}", i)?;
+            writeln!(f, "H_{}:\n{}", i, block.chaining)?;
+            writeln!(f, "m_{}:\n{}", i, block.message)?;
+            for state in block.states.iter() {
+                writeln!(f, "round {} {}", state.round, state.step)?;
+                writeln!(f, "key:\n{}", state.key)?;
+                writeln!(f, "data:\n{}", state.data)?;
+            }
+            writeln!(f, "H_{}:\n{}", i + 1, block.output)?;
+            writeln!(f)?;
+        }
+        writeln!(f, "digest: {}", self.digest)
+    }
+}
+
+fn json_matrix<const N: usize, const M: u16>(matrix: &Matrix<N, M>) -> String {
+    let rows: Vec<String> = matrix
+        .0
+        .iter()
+        .map(|row| {
+            format!(
+                "\"{}\"",
+                to_hex(&row.iter().map(|x| x.0).collect::<Vec<u8>>())
+            )
+        })
+        .collect();
+    format!("[{}]", rows.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{hash, hash_traced};
+    use crate::params::WhirlpoolParams;
+
+    #[test]
+    fn test_trace() {
+        let params = WhirlpoolParams::mini();
+        let input = b"Ala ma kota, kot ma ale.".to_vec();
+        let trace = hash_traced(&params, input.clone()).unwrap();
+        assert_eq!(trace.digest, hash(&params, input).unwrap());
+        assert_eq!(trace.blocks.len(), 2);
+
+        let steps = [
+            Step::SubBytes,
+            Step::ShiftColumns,
+            Step::MixRows,
+            Step::AddKey,
+        ];
+        for (i, block) in trace.blocks.iter().enumerate() {
+            assert_eq!(block.states.len(), 1 + 4 * params.rounds());
+            let first = block.states[0];
+            assert_eq!((first.round, first.step), (0, Step::AddKey));
+            assert_eq!(first.key, block.chaining);
+            assert_eq!(first.data, block.message + block.chaining);
+            for (j, state) in block.states[1..].iter().enumerate() {
+                assert_eq!((state.round, state.step), (1 + j / 4, steps[j % 4]));
+            }
+            let last = block.states[block.states.len() - 1];
+            assert_eq!(last.data + block.message + block.chaining, block.output);
+            if i > 0 {
+                assert_eq!(block.chaining, trace.blocks[i - 1].output);
+            }
+        }
+        assert_eq!(trace.blocks[1].output.to_bytes(), trace.digest.as_ref());
+    }
+
+    #[test]
+    fn test_trace_iso() {
+        let params = WhirlpoolParams::iso();
+        let trace = hash_traced(&params, b"abc".to_vec()).unwrap();
+        assert_eq!(trace.digest, hash(&params, b"abc".to_vec()).unwrap());
+        assert_eq!(trace.blocks.len(), 1);
+        assert_eq!(trace.blocks[0].states.len(), 41);
+    }
+
+    #[test]
+    fn test_dump() {
+        let params = WhirlpoolParams::mini();
+        let trace = hash_traced(&params, Vec::new()).unwrap();
+
+        let text = trace.to_string();
+        assert!(text.starts_with("block 0\nH_0:\n0, 0, 0, 0\n"));
+        assert!(text.contains("round 6 MR\nkey:\n"));
+        assert!(text.ends_with(&format!("digest: {}\n", trace.digest)));
+
+        let json = trace.to_json();
+        assert!(json.starts_with(&format!(
+            "{{\"digest\":\"{}\",\"blocks\":[{{\"block\":0,\"chaining\":[\"00000000\",",
+            trace.digest
+        )));
+        assert_eq!(json.matches("\"round\":").count(), 25);
+        assert_eq!(json.matches("\"step\":\"SC\"").count(), 6);
+        assert!(json.ends_with("]}]}"));
+    }
+}